@@ -0,0 +1,279 @@
+use {
+    super::{play_bingo, rng::Rng, Board, Number},
+    itertools::Itertools,
+    std::{fmt, ops::RangeInclusive},
+};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(super) enum Uniqueness {
+    /// No number appears twice on the same board.
+    WithinBoard,
+    /// No number appears on more than one board.
+    AcrossBoards,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(super) enum Outcome {
+    WinsFirst(usize),
+    WinsLast(usize),
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct Config {
+    pub(super) boards: usize,
+    pub(super) board_size: usize,
+    pub(super) numbers: RangeInclusive<i32>,
+    pub(super) uniqueness: Uniqueness,
+    pub(super) outcome: Option<Outcome>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            boards: 100,
+            board_size: 5,
+            numbers: 0..=99,
+            uniqueness: Uniqueness::WithinBoard,
+            outcome: None,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(super) enum GenerateError {
+    NotEnoughNumbers,
+    NoSuchBoard,
+    /// No draw order giving the requested outcome was found within the attempt limit.
+    Unsatisfiable,
+}
+
+/// A bingo input that `parse_input` reads back as the same draws and boards.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct Generated {
+    pub(super) draws: Vec<i32>,
+    pub(super) boards: Vec<Vec<i32>>,
+    board_size: usize,
+}
+
+impl fmt::Display for Generated {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.draws.iter().join(","))?;
+
+        let width = self
+            .boards
+            .iter()
+            .flatten()
+            .map(|number| number.to_string().len())
+            .max()
+            .unwrap_or(1);
+        for board in &self.boards {
+            writeln!(f)?;
+            for row in board.chunks(self.board_size) {
+                writeln!(f)?;
+                write!(
+                    f,
+                    "{}",
+                    row.iter()
+                        .map(|number| format!("{:>width$}", number, width = width))
+                        .join(" ")
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+const ATTEMPTS: usize = 100;
+
+pub(super) fn generate(config: &Config, rng: &mut Rng) -> Result<Generated, GenerateError> {
+    let cells = config.board_size * config.board_size;
+    let available = config.numbers.clone().count();
+    let needed = match config.uniqueness {
+        Uniqueness::WithinBoard => cells,
+        Uniqueness::AcrossBoards => cells * config.boards,
+    };
+    if cells == 0 || available < needed {
+        return Err(GenerateError::NotEnoughNumbers);
+    }
+    if let Some(Outcome::WinsFirst(board) | Outcome::WinsLast(board)) = config.outcome {
+        if board >= config.boards {
+            return Err(GenerateError::NoSuchBoard);
+        }
+    }
+
+    for _ in 0..ATTEMPTS {
+        let boards = generate_boards(config, rng);
+        let draws = match config.outcome {
+            None => shuffled(config.numbers.clone(), rng),
+            Some(Outcome::WinsFirst(target)) => draws_won_first_by(&boards[target], config, rng),
+            Some(Outcome::WinsLast(target)) => draws_won_last_by(&boards[target], config, rng),
+        };
+
+        let generated = Generated {
+            draws,
+            boards,
+            board_size: config.board_size,
+        };
+        if config
+            .outcome
+            .is_none_or(|outcome| generated.has_outcome(outcome))
+        {
+            return Ok(generated);
+        }
+    }
+
+    Err(GenerateError::Unsatisfiable)
+}
+
+fn shuffled(numbers: impl Iterator<Item = i32>, rng: &mut Rng) -> Vec<i32> {
+    let mut numbers = numbers.collect::<Vec<_>>();
+    rng.shuffle(&mut numbers);
+    numbers
+}
+
+fn generate_boards(config: &Config, rng: &mut Rng) -> Vec<Vec<i32>> {
+    let cells = config.board_size * config.board_size;
+    match config.uniqueness {
+        Uniqueness::WithinBoard => (0..config.boards)
+            .map(|_| {
+                let mut numbers = shuffled(config.numbers.clone(), rng);
+                numbers.truncate(cells);
+                numbers
+            })
+            .collect(),
+        Uniqueness::AcrossBoards => shuffled(config.numbers.clone(), rng)
+            .chunks(cells)
+            .take(config.boards)
+            .map(<[i32]>::to_vec)
+            .collect(),
+    }
+}
+
+/// One of the target's rows or columns first, then everything else.
+fn draws_won_first_by(target: &[i32], config: &Config, rng: &mut Rng) -> Vec<i32> {
+    let size = config.board_size;
+    let line = rng.below(2 * size);
+    let mut draws = shuffled(
+        (0..size).map(|i| match line {
+            row if row < size => target[row * size + i],
+            column => target[i * size + (column - size)],
+        }),
+        rng,
+    );
+    draws.extend(shuffled(
+        config
+            .numbers
+            .clone()
+            .filter(|number| !draws.contains(number)),
+        rng,
+    ));
+    draws
+}
+
+/// Everything not on the target first, then the target's own numbers.
+fn draws_won_last_by(target: &[i32], config: &Config, rng: &mut Rng) -> Vec<i32> {
+    let mut draws = shuffled(
+        config
+            .numbers
+            .clone()
+            .filter(|number| !target.contains(number)),
+        rng,
+    );
+    draws.extend(shuffled(target.iter().copied(), rng));
+    draws
+}
+
+impl Generated {
+    fn parsed_boards(&self) -> Vec<Board> {
+        self.boards
+            .iter()
+            .map(|board| Board::new(board.iter().copied().map(Number::Unmarked).collect()))
+            .collect()
+    }
+
+    /// Whether the outcome holds without relying on ties between boards completing on the same draw.
+    fn has_outcome(&self, outcome: Outcome) -> bool {
+        let wins = play_bingo(self.draws.clone(), self.parsed_boards()).0;
+        match outcome {
+            Outcome::WinsFirst(target) => match wins.as_slice() {
+                [first, rest @ ..] => {
+                    first.board == target && rest.first().is_none_or(|next| next.draw > first.draw)
+                }
+                [] => false,
+            },
+            Outcome::WinsLast(target) => {
+                wins.len() == self.boards.len()
+                    && match wins.as_slice() {
+                        [.., previous, last] => last.board == target && previous.draw < last.draw,
+                        [only] => only.board == target,
+                        [] => false,
+                    }
+            }
+        }
+    }
+}
+
+#[test]
+fn round_trips_through_parse_input() {
+    let config = Config {
+        boards: 10,
+        board_size: 4,
+        numbers: 1..=40,
+        ..Config::default()
+    };
+    let generated = generate(&config, &mut Rng::seeded(3)).unwrap();
+
+    let (draws, boards) = super::parse_input(&generated.to_string());
+
+    assert_eq!(draws, generated.draws);
+    assert_eq!(boards.len(), 10);
+    assert!(boards.iter().all(|board| board.size == 4));
+}
+
+#[test]
+fn chosen_board_wins_first_and_last() {
+    let mut rng = Rng::seeded(11);
+    for outcome in [Outcome::WinsFirst(7), Outcome::WinsLast(7)] {
+        for uniqueness in [Uniqueness::WithinBoard, Uniqueness::AcrossBoards] {
+            let config = Config {
+                boards: 12,
+                numbers: 0..=299,
+                uniqueness,
+                outcome: Some(outcome),
+                ..Config::default()
+            };
+            let generated = generate(&config, &mut rng).unwrap();
+
+            let (draws, boards) = super::parse_input(&generated.to_string());
+            let wins = play_bingo(draws, boards).0;
+            let winner = match outcome {
+                Outcome::WinsFirst(_) => wins.first(),
+                Outcome::WinsLast(_) => wins.last(),
+            };
+            assert_eq!(winner.unwrap().board, 7);
+        }
+    }
+}
+
+#[test]
+fn rejects_impossible_configs() {
+    let config = Config {
+        boards: 5,
+        numbers: 1..=100,
+        uniqueness: Uniqueness::AcrossBoards,
+        ..Config::default()
+    };
+    assert_eq!(
+        generate(&config, &mut Rng::seeded(0)),
+        Err(GenerateError::NotEnoughNumbers)
+    );
+
+    let config = Config {
+        outcome: Some(Outcome::WinsLast(100)),
+        ..Config::default()
+    };
+    assert_eq!(
+        generate(&config, &mut Rng::seeded(0)),
+        Err(GenerateError::NoSuchBoard)
+    );
+}
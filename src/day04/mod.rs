@@ -1,3 +1,9 @@
+mod generator;
+mod rng;
+mod simulation;
+
+use std::collections::HashMap;
+
 #[derive(Debug, Copy, Clone)]
 enum Number {
     Unmarked(i32),
@@ -10,59 +16,50 @@ impl Number {
     }
 }
 
-struct Board([Number; 25]);
+#[derive(Clone)]
+struct Board {
+    size: usize,
+    numbers: Vec<Number>,
+    marked_in_row: Vec<usize>,
+    marked_in_column: Vec<usize>,
+    has_won: bool,
+}
 
 impl Board {
-    const N: usize = 5;
-
-    fn mark_number(&mut self, drawn_number: i32) {
-        for number in &mut self.0 {
-            if let &mut Number::Unmarked(n) = number {
-                if n == drawn_number {
-                    *number = Number::Marked;
-                }
-            }
+    fn new(numbers: Vec<Number>) -> Self {
+        let size = (numbers.len() as f64).sqrt() as usize;
+        assert_eq!(size * size, numbers.len(), "board is square");
+
+        Self {
+            size,
+            numbers,
+            marked_in_row: vec![0; size],
+            marked_in_column: vec![0; size],
+            has_won: false,
         }
     }
 
-    fn row(&self, row: usize) -> impl Iterator<Item = Number> + '_ {
-        (row * Self::N..)
-            .take(Self::N)
-            .map(|i| self.0.get(i).cloned())
-            .flatten()
-    }
-
-    fn rows(&self) -> impl Iterator<Item = impl Iterator<Item = Number> + '_> + '_ {
-        (0..Self::N).map(|i| self.row(i))
-    }
-
-    fn column(&self, column: usize) -> impl Iterator<Item = Number> + '_ {
-        (column..)
-            .step_by(Self::N)
-            .take(Self::N)
-            .map(|i| self.0.get(i).cloned())
-            .flatten()
-    }
-
-    fn columns(&self) -> impl Iterator<Item = impl Iterator<Item = Number> + '_> + '_ {
-        (0..Self::N).map(|i| self.column(i))
-    }
-
-    fn any_winning_rows(&self) -> bool {
-        self.rows().any(|mut row| row.all(Number::is_marked))
-    }
+    fn mark_cell(&mut self, cell: usize) {
+        let number = &mut self.numbers[cell];
+        if number.is_marked() {
+            return;
+        }
+        *number = Number::Marked;
 
-    fn any_winning_columns(&self) -> bool {
-        self.columns()
-            .any(|mut column| column.all(Number::is_marked))
+        let (row, column) = (cell / self.size, cell % self.size);
+        self.marked_in_row[row] += 1;
+        self.marked_in_column[column] += 1;
+        if self.marked_in_row[row] == self.size || self.marked_in_column[column] == self.size {
+            self.has_won = true;
+        }
     }
 
     fn is_winner(&self) -> bool {
-        self.any_winning_rows() || self.any_winning_columns()
+        self.has_won
     }
 
     fn sum_of_unmarked_numbers(&self) -> i32 {
-        self.0
+        self.numbers
             .iter()
             .filter_map(|number| match number {
                 &Number::Unmarked(n) => Some(n),
@@ -76,33 +73,89 @@ impl Board {
     }
 }
 
-struct BingoScores(Vec<i32>);
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct Occurrence {
+    board: usize,
+    cell: usize,
+}
+
+/// Where each number appears across all boards, ordered by board.
+struct NumberIndex(HashMap<i32, Vec<Occurrence>>);
+
+impl NumberIndex {
+    fn new(boards: &[Board]) -> Self {
+        let mut index = HashMap::<i32, Vec<Occurrence>>::new();
+        for (board, numbers) in boards.iter().map(|board| &board.numbers).enumerate() {
+            for (cell, number) in numbers.iter().enumerate() {
+                if let &Number::Unmarked(n) = number {
+                    index.entry(n).or_default().push(Occurrence { board, cell });
+                }
+            }
+        }
+        Self(index)
+    }
+
+    fn occurrences(&self, number: i32) -> &[Occurrence] {
+        self.0.get(&number).map_or(&[], Vec::as_slice)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct Win {
+    board: usize,
+    draw: usize,
+    score: i32,
+}
+
+struct BingoScores(Vec<Win>);
 
 impl BingoScores {
     fn winning_score(&self) -> i32 {
-        *self.0.first().unwrap()
+        self.0.first().unwrap().score
     }
 
     fn losing_score(&self) -> i32 {
-        *self.0.last().unwrap()
+        self.0.last().unwrap().score
     }
 }
 
 fn play_bingo(numbers: Vec<i32>, mut boards: Vec<Board>) -> BingoScores {
+    let index = NumberIndex::new(&boards);
+    let mut finished = vec![false; boards.len()];
+
     let mut scores = BingoScores(vec![]);
-    for number_drawn in numbers {
-        for board in &mut boards {
-            board.mark_number(number_drawn);
-            if board.is_winner() {
-                scores.0.push(board.score(number_drawn));
+    for (draw, number_drawn) in numbers.into_iter().enumerate() {
+        let occurrences = index.occurrences(number_drawn);
+        for &Occurrence { board, cell } in occurrences {
+            if !finished[board] {
+                boards[board].mark_cell(cell);
             }
         }
 
-        boards.retain(|board| !board.is_winner())
+        for &Occurrence { board, .. } in occurrences {
+            if !finished[board] && boards[board].is_winner() {
+                finished[board] = true;
+                scores.0.push(Win {
+                    board,
+                    draw,
+                    score: boards[board].score(number_drawn),
+                });
+            }
+        }
     }
     scores
 }
 
+#[test]
+fn example() {
+    let (numbers, boards) = parse_input(include_str!("example.txt"));
+
+    let scores = play_bingo(numbers, boards);
+
+    assert_eq!(scores.winning_score(), 4512);
+    assert_eq!(scores.losing_score(), 1924);
+}
+
 #[test]
 fn solve() {
     let (numbers, boards) = parse_input(include_str!("input.txt"));
@@ -113,7 +166,7 @@ fn solve() {
     assert_eq!(scores.losing_score(), 31892);
 }
 
-fn parse_input(input: &'static str) -> (Vec<i32>, Vec<Board>) {
+fn parse_input(input: &str) -> (Vec<i32>, Vec<Board>) {
     use itertools::Itertools;
 
     let mut lines = input.lines();
@@ -126,14 +179,17 @@ fn parse_input(input: &'static str) -> (Vec<i32>, Vec<Board>) {
         .collect::<Vec<_>>();
 
     let mut boards = Vec::new();
-    for chunk in &lines.chunks(6) {
-        let board = chunk.skip(1).fold(Vec::new(), |mut board, line| {
-            for x in line.trim().split_whitespace() {
+    for (is_blank, chunk) in &lines.group_by(|line| line.trim().is_empty()) {
+        if is_blank {
+            continue;
+        }
+        let board = chunk.fold(Vec::new(), |mut board, line| {
+            for x in line.split_whitespace() {
                 board.push(Number::Unmarked(x.parse().unwrap()));
             }
             board
         });
-        boards.push(Board(board.try_into().expect("board size is 5x5")));
+        boards.push(Board::new(board));
     }
 
     (numbers, boards)
@@ -0,0 +1,53 @@
+/// A small SplitMix64 generator, so runs are reproducible from a seed without pulling in `rand`.
+#[derive(Debug, Clone)]
+pub(super) struct Rng(u64);
+
+impl Rng {
+    pub(super) fn seeded(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub(super) fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `0..bound`, rejecting the biased tail of the `u64` range.
+    pub(super) fn below(&mut self, bound: usize) -> usize {
+        assert!(bound > 0, "bound must be positive");
+        let bound = bound as u64;
+        let zone = u64::MAX - (u64::MAX % bound);
+        loop {
+            let n = self.next_u64();
+            if n < zone {
+                return (n % bound) as usize;
+            }
+        }
+    }
+
+    /// Fisher-Yates shuffle.
+    pub(super) fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+}
+
+#[test]
+fn same_seed_same_sequence() {
+    let (mut a, mut b) = (Rng::seeded(42), Rng::seeded(42));
+    assert!((0..100).all(|_| a.next_u64() == b.next_u64()));
+}
+
+#[test]
+fn shuffle_is_a_permutation() {
+    let mut items = (0..50).collect::<Vec<_>>();
+    Rng::seeded(7).shuffle(&mut items);
+    assert_ne!(items, (0..50).collect::<Vec<_>>());
+
+    items.sort_unstable();
+    assert_eq!(items, (0..50).collect::<Vec<_>>());
+}
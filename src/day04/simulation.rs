@@ -0,0 +1,96 @@
+use super::{play_bingo, rng::Rng, Board};
+
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub(super) struct BoardOdds {
+    pub(super) wins_first: f64,
+    pub(super) wins_last: f64,
+    /// Mean draw index at which the board completes, over the runs in which it completes at all.
+    pub(super) expected_winning_draw: Option<f64>,
+}
+
+#[derive(Debug)]
+pub(super) struct Simulation {
+    pub(super) runs: usize,
+    pub(super) odds: Vec<BoardOdds>,
+}
+
+#[derive(Default, Copy, Clone)]
+struct Tally {
+    first: usize,
+    last: usize,
+    completed: usize,
+    sum_of_draws: usize,
+}
+
+/// Plays `runs` games of bingo, each with `numbers` drawn in a freshly shuffled order.
+///
+/// Panics if `runs` is zero, as there would be no odds to report.
+pub(super) fn simulate(
+    numbers: &[i32],
+    boards: &[Board],
+    runs: usize,
+    rng: &mut Rng,
+) -> Simulation {
+    assert!(runs > 0, "at least one run is needed");
+    let mut tallies = vec![Tally::default(); boards.len()];
+    let mut draw_order = numbers.to_vec();
+
+    for _ in 0..runs {
+        rng.shuffle(&mut draw_order);
+        let scores = play_bingo(draw_order.clone(), boards.to_vec());
+
+        for win in &scores.0 {
+            tallies[win.board].completed += 1;
+            tallies[win.board].sum_of_draws += win.draw;
+        }
+        if let (Some(first), Some(last)) = (scores.0.first(), scores.0.last()) {
+            tallies[first.board].first += 1;
+            tallies[last.board].last += 1;
+        }
+    }
+
+    let odds = tallies
+        .iter()
+        .map(|tally| BoardOdds {
+            wins_first: tally.first as f64 / runs as f64,
+            wins_last: tally.last as f64 / runs as f64,
+            expected_winning_draw: (tally.completed > 0)
+                .then(|| tally.sum_of_draws as f64 / tally.completed as f64),
+        })
+        .collect();
+
+    Simulation { runs, odds }
+}
+
+#[test]
+fn example_odds() {
+    let (numbers, boards) = super::parse_input(include_str!("example.txt"));
+
+    let simulation = simulate(&numbers, &boards, 1000, &mut Rng::seeded(2021));
+    assert_eq!(simulation.runs, 1000);
+
+    let total = |odds: fn(&BoardOdds) -> f64| simulation.odds.iter().map(odds).sum::<f64>();
+    assert!((total(|odds| odds.wins_first) - 1.0).abs() < 1e-9);
+    assert!((total(|odds| odds.wins_last) - 1.0).abs() < 1e-9);
+    assert!(simulation
+        .odds
+        .iter()
+        .all(|odds| odds.expected_winning_draw.unwrap() < numbers.len() as f64));
+}
+
+#[test]
+#[should_panic(expected = "at least one run is needed")]
+fn simulation_needs_a_run() {
+    let (numbers, boards) = super::parse_input(include_str!("example.txt"));
+    simulate(&numbers, &boards, 0, &mut Rng::seeded(2021));
+}
+
+#[test]
+fn simulation_is_reproducible_from_seed() {
+    let (numbers, boards) = super::parse_input(include_str!("input.txt"));
+
+    let a = simulate(&numbers, &boards, 20, &mut Rng::seeded(1));
+    let b = simulate(&numbers, &boards, 20, &mut Rng::seeded(1));
+
+    assert_eq!(a.odds, b.odds);
+}
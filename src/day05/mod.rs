@@ -1,6 +1,9 @@
+mod render;
+mod sweep;
+
 use itertools::Itertools;
 use parse_display::{Display, FromStr};
-use std::{iter::successors, ops::Add};
+use std::{collections::HashMap, iter::successors, ops::Add};
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Display, FromStr)]
 #[display("{x},{y}")]
@@ -27,12 +30,68 @@ struct Line {
     stop: Point,
 }
 
+/// An inclusive rectangle of the vent field. `min` and `max` may be given as any two opposite
+/// corners.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct Region {
+    min: Point,
+    max: Point,
+}
+
+impl Region {
+    /// The same rectangle with `min` and `max` swapped where needed, so it is never empty.
+    fn normalised(self) -> Self {
+        Self {
+            min: Point {
+                x: self.min.x.min(self.max.x),
+                y: self.min.y.min(self.max.y),
+            },
+            max: Point {
+                x: self.min.x.max(self.max.x),
+                y: self.min.y.max(self.max.y),
+            },
+        }
+    }
+
+    fn contains(&self, point: Point) -> bool {
+        let Region { min, max } = self.normalised();
+        (min.x..=max.x).contains(&point.x) && (min.y..=max.y).contains(&point.y)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 enum Direction {
     Horizontal,
     Vertical,
     Diagonal,
 }
 
+impl Direction {
+    const ALL: [Direction; 3] = [
+        Direction::Horizontal,
+        Direction::Vertical,
+        Direction::Diagonal,
+    ];
+
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    fn is_axis_aligned(&self) -> bool {
+        matches!(self, Direction::Horizontal | Direction::Vertical)
+    }
+}
+
+/// How a line is turned into grid points when it isn't horizontal, vertical or at 45°.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Rasterisation {
+    /// One point per step along the major axis.
+    Bresenham,
+    /// Every grid cell the line passes through, including both cells either side of a corner it
+    /// crosses exactly.
+    Supercover,
+}
+
 impl Line {
     fn direction(&self) -> Direction {
         match (self.start, self.stop) {
@@ -42,41 +101,94 @@ impl Line {
         }
     }
 
-    pub fn delta(&self) -> Point {
-        Point {
-            x: match self.direction() {
-                Direction::Horizontal | Direction::Diagonal => {
-                    if self.start.x < self.stop.x {
-                        1
-                    } else {
-                        -1
-                    }
-                }
-                Direction::Vertical => 0,
-            },
-            y: match self.direction() {
-                Direction::Vertical | Direction::Diagonal => {
-                    if self.start.y < self.stop.y {
-                        1
-                    } else {
-                        -1
-                    }
-                }
-                Direction::Horizontal => 0,
-            },
-        }
+    /// Whether the line is horizontal, vertical or at exactly 45°, as the puzzle promises.
+    fn is_octilinear(&self) -> bool {
+        let (dx, dy) = (self.stop.x - self.start.x, self.stop.y - self.start.y);
+        dx == 0 || dy == 0 || dx.abs() == dy.abs()
     }
 
     fn points(&self) -> impl Iterator<Item = Point> {
-        let line = *self;
-        successors(Some(self.start), move |&last| {
-            if line.stop == last {
-                None
-            } else {
-                Some(last + line.delta())
+        self.bresenham()
+    }
+
+    fn rasterise(&self, rasterisation: Rasterisation) -> Box<dyn Iterator<Item = Point>> {
+        match rasterisation {
+            Rasterisation::Bresenham => Box::new(self.bresenham()),
+            Rasterisation::Supercover => Box::new(self.supercover()),
+        }
+    }
+
+    fn bresenham(&self) -> impl Iterator<Item = Point> {
+        let (start, stop) = (self.start, self.stop);
+        let (dx, dy) = ((stop.x - start.x).abs(), -(stop.y - start.y).abs());
+        let step = Point {
+            x: (stop.x - start.x).signum(),
+            y: (stop.y - start.y).signum(),
+        };
+
+        let mut error = dx + dy;
+        successors(Some(start), move |&last| {
+            if last == stop {
+                return None;
+            }
+
+            let (mut next, doubled_error) = (last, 2 * error);
+            if doubled_error >= dy {
+                error += dy;
+                next.x += step.x;
             }
+            if doubled_error <= dx {
+                error += dx;
+                next.y += step.y;
+            }
+            Some(next)
         })
     }
+
+    fn supercover(&self) -> impl Iterator<Item = Point> {
+        let start = self.start;
+        let (nx, ny) = (
+            (self.stop.x - start.x).unsigned_abs() as i64,
+            (self.stop.y - start.y).unsigned_abs() as i64,
+        );
+        let step = Point {
+            x: (self.stop.x - start.x).signum(),
+            y: (self.stop.y - start.y).signum(),
+        };
+
+        let (mut ix, mut iy, mut current) = (0, 0, start);
+        let steps = std::iter::from_fn(move || {
+            if ix == nx && iy == ny {
+                return None;
+            }
+
+            let decision = (1 + 2 * ix) * ny - (1 + 2 * iy) * nx;
+            let corner = if decision == 0 {
+                ix += 1;
+                iy += 1;
+                [
+                    Some(current + Point { x: step.x, y: 0 }),
+                    Some(current + Point { x: 0, y: step.y }),
+                ]
+            } else {
+                if decision < 0 {
+                    ix += 1;
+                } else {
+                    iy += 1;
+                }
+                [None, None]
+            };
+
+            current = start
+                + Point {
+                    x: step.x * ix as i32,
+                    y: step.y * iy as i32,
+                };
+            Some(corner.into_iter().flatten().chain(Some(current)))
+        });
+
+        Some(start).into_iter().chain(steps.flatten())
+    }
 }
 
 fn parse_input(input: &'static str) -> impl Iterator<Item = Line> {
@@ -84,33 +196,198 @@ fn parse_input(input: &'static str) -> impl Iterator<Item = Line> {
 }
 
 #[test]
-fn solve_one() {
+fn lines_at_any_angle_terminate() {
+    let line = "0,0 -> 5,2".parse::<Line>().unwrap();
+    assert!(!line.is_octilinear());
+
     assert_eq!(
-        parse_input(include_str!("input.txt"))
-            .filter(|line| matches!(
-                line.direction(),
-                Direction::Horizontal | Direction::Vertical
-            ))
-            .map(|line| line.points())
-            .flatten()
-            .counts()
-            .iter()
-            .filter(|(_, &count)| count >= 2)
-            .count(),
-        6397
+        line.rasterise(Rasterisation::Bresenham)
+            .map(|point| point.to_string())
+            .collect_vec(),
+        ["0,0", "1,0", "2,1", "3,1", "4,2", "5,2"]
+    );
+    assert_eq!(
+        line.rasterise(Rasterisation::Supercover)
+            .map(|point| point.to_string())
+            .collect_vec(),
+        ["0,0", "1,0", "1,1", "2,1", "3,1", "4,1", "4,2", "5,2"]
     );
 }
 
 #[test]
-fn solve_two() {
-    assert_eq!(
-        parse_input(include_str!("input.txt"))
-            .map(|line| line.points())
-            .flatten()
-            .counts()
+fn octilinear_lines_rasterise_to_unit_steps() {
+    for line in parse_input(include_str!("example.txt")) {
+        assert!(line.is_octilinear());
+
+        let points = line.points().collect_vec();
+        let length = (line.stop.x - line.start.x)
+            .abs()
+            .max((line.stop.y - line.start.y).abs());
+        assert_eq!(points.len(), length as usize + 1);
+        assert_eq!(points.last(), Some(&line.stop));
+    }
+}
+
+/// Which lines and which part of the field a [`VentField`] query considers.
+#[derive(Debug, Copy, Clone)]
+struct Query<'a> {
+    directions: &'a [Direction],
+    region: Option<Region>,
+}
+
+impl Default for Query<'_> {
+    fn default() -> Self {
+        Self {
+            directions: &Direction::ALL,
+            region: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Peak {
+    coverage: usize,
+    points: Vec<Point>,
+}
+
+/// How many vents of each direction cover every point.
+struct VentField(HashMap<Point, [usize; 3]>);
+
+impl VentField {
+    fn new(lines: impl IntoIterator<Item = Line>) -> Self {
+        let mut coverage = HashMap::<Point, [usize; 3]>::new();
+        for line in lines {
+            let direction = line.direction().index();
+            for point in line.points() {
+                coverage.entry(point).or_default()[direction] += 1;
+            }
+        }
+        Self(coverage)
+    }
+
+    fn covered_points<'a>(&'a self, query: &'a Query) -> impl Iterator<Item = (Point, usize)> + 'a {
+        self.0
             .iter()
-            .filter(|(_, &count)| count >= 2)
-            .count(),
-        22335
+            .filter(move |(point, _)| query.region.is_none_or(|region| region.contains(**point)))
+            .map(move |(&point, coverage)| (point, Self::sum(coverage, query)))
+            .filter(|&(_, coverage)| coverage > 0)
+    }
+
+    fn sum(coverage: &[usize; 3], query: &Query) -> usize {
+        query
+            .directions
+            .iter()
+            .map(|direction| coverage[direction.index()])
+            .sum()
+    }
+
+    fn coverage_at(&self, point: Point, query: &Query) -> usize {
+        match self.0.get(&point) {
+            Some(coverage) if query.region.is_none_or(|region| region.contains(point)) => {
+                Self::sum(coverage, query)
+            }
+            _ => 0,
+        }
+    }
+
+    fn count_at_least(&self, threshold: usize, query: &Query) -> usize {
+        self.covered_points(query)
+            .filter(|&(_, coverage)| coverage >= threshold)
+            .count()
+    }
+
+    /// The highest coverage and every point that reaches it, ordered by row then column.
+    fn peak(&self, query: &Query) -> Option<Peak> {
+        let coverage = self
+            .covered_points(query)
+            .map(|(_, coverage)| coverage)
+            .max()?;
+
+        Some(Peak {
+            coverage,
+            points: self
+                .covered_points(query)
+                .filter(|&(_, c)| c == coverage)
+                .map(|(point, _)| point)
+                .sorted_by_key(|point| (point.y, point.x))
+                .collect(),
+        })
+    }
+}
+
+#[test]
+fn example_queries() {
+    let field = VentField::new(parse_input(include_str!("example.txt")));
+    let axis_aligned = Query {
+        directions: &[Direction::Horizontal, Direction::Vertical],
+        ..Query::default()
+    };
+    let top_left = Query {
+        region: Some(Region {
+            min: Point { x: 0, y: 0 },
+            max: Point { x: 4, y: 4 },
+        }),
+        ..Query::default()
+    };
+
+    assert_eq!(field.count_at_least(2, &axis_aligned), 5);
+    assert_eq!(field.count_at_least(2, &Query::default()), 12);
+    assert_eq!(field.count_at_least(2, &top_left), 3);
+
+    let flipped = Query {
+        region: top_left.region.map(|region| Region {
+            min: Point {
+                x: region.max.x,
+                y: region.min.y,
+            },
+            max: Point {
+                x: region.min.x,
+                y: region.max.y,
+            },
+        }),
+        ..Query::default()
+    };
+    assert_eq!(field.count_at_least(2, &flipped), 3);
+    assert_eq!(field.coverage_at(Point { x: 4, y: 4 }, &flipped), 3);
+
+    assert_eq!(
+        field.coverage_at(Point { x: 4, y: 4 }, &Query::default()),
+        3
+    );
+    assert_eq!(field.coverage_at(Point { x: 4, y: 4 }, &axis_aligned), 1);
+    assert_eq!(field.coverage_at(Point { x: 9, y: 9 }, &top_left), 0);
+
+    assert_eq!(
+        field.peak(&Query::default()),
+        Some(Peak {
+            coverage: 3,
+            points: vec![Point { x: 4, y: 4 }, Point { x: 6, y: 4 }],
+        })
+    );
+    assert_eq!(field.peak(&axis_aligned).unwrap().coverage, 2);
+    assert_eq!(
+        field.peak(&Query {
+            directions: &[],
+            ..Query::default()
+        }),
+        None
     );
 }
+
+#[test]
+fn solve_one() {
+    let field = VentField::new(parse_input(include_str!("input.txt")));
+    let axis_aligned = Query {
+        directions: &[Direction::Horizontal, Direction::Vertical],
+        ..Query::default()
+    };
+
+    assert_eq!(field.count_at_least(2, &axis_aligned), 6397);
+}
+
+#[test]
+fn solve_two() {
+    let field = VentField::new(parse_input(include_str!("input.txt")));
+
+    assert_eq!(field.count_at_least(2, &Query::default()), 22335);
+}
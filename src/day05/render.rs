@@ -0,0 +1,234 @@
+use {
+    super::{Line, Point, Region},
+    itertools::Itertools,
+    std::collections::HashMap,
+};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(super) enum LineFilter {
+    AxisAligned,
+    All,
+}
+
+impl LineFilter {
+    fn accepts(self, line: &Line) -> bool {
+        match self {
+            Self::AxisAligned => line.direction().is_axis_aligned(),
+            Self::All => true,
+        }
+    }
+}
+
+impl Region {
+    /// The smallest region containing the origin and every line, as in the puzzle's diagrams.
+    fn fitting<'a>(lines: impl Iterator<Item = &'a Line>) -> Self {
+        let origin = Point { x: 0, y: 0 };
+        lines.flat_map(|line| [line.start, line.stop]).fold(
+            Self {
+                min: origin,
+                max: origin,
+            },
+            |region, point| Self {
+                min: Point {
+                    x: region.min.x.min(point.x),
+                    y: region.min.y.min(point.y),
+                },
+                max: Point {
+                    x: region.max.x.max(point.x),
+                    y: region.max.y.max(point.y),
+                },
+            },
+        )
+    }
+
+    fn width(&self) -> usize {
+        (self.max.x - self.min.x + 1) as usize
+    }
+
+    fn height(&self) -> usize {
+        (self.max.y - self.min.y + 1) as usize
+    }
+
+    fn rows(self) -> impl Iterator<Item = impl Iterator<Item = Point>> {
+        (self.min.y..=self.max.y)
+            .map(move |y| (self.min.x..=self.max.x).map(move |x| Point { x, y }))
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(super) enum ImageFormat {
+    /// Greyscale, brighter where more vents overlap.
+    Pgm,
+    /// Colour heatmap running from black through red and yellow to white.
+    Ppm,
+}
+
+struct Coverage {
+    viewport: Region,
+    counts: HashMap<Point, usize>,
+}
+
+impl Coverage {
+    fn new(lines: &[Line], filter: LineFilter, viewport: Option<Region>) -> Self {
+        let lines = lines
+            .iter()
+            .filter(|line| filter.accepts(line))
+            .collect_vec();
+        let viewport = viewport
+            .map(Region::normalised)
+            .unwrap_or_else(|| Region::fitting(lines.iter().copied()));
+        let counts = lines
+            .iter()
+            .flat_map(|line| line.points())
+            .filter(|&point| viewport.contains(point))
+            .counts();
+
+        Self { viewport, counts }
+    }
+
+    fn at(&self, point: Point) -> usize {
+        self.counts.get(&point).copied().unwrap_or_default()
+    }
+
+    fn max(&self) -> usize {
+        self.counts.values().copied().max().unwrap_or_default()
+    }
+}
+
+/// The puzzle's diagram: `.` where no vents lie, otherwise the number of overlapping vents, with
+/// `+` standing in for ten or more.
+pub(super) fn render_text(lines: &[Line], filter: LineFilter, viewport: Option<Region>) -> String {
+    let coverage = Coverage::new(lines, filter, viewport);
+
+    coverage
+        .viewport
+        .rows()
+        .map(|row| {
+            row.map(|point| match coverage.at(point) {
+                0 => '.',
+                n @ 1..=9 => char::from_digit(n as u32, 10).unwrap(),
+                _ => '+',
+            })
+            .chain(Some('\n'))
+            .collect::<String>()
+        })
+        .collect()
+}
+
+/// A binary (`P5`/`P6`) netpbm image of the coverage, scaled so the busiest point is brightest.
+pub(super) fn render_image(
+    lines: &[Line],
+    filter: LineFilter,
+    viewport: Option<Region>,
+    format: ImageFormat,
+) -> Vec<u8> {
+    let coverage = Coverage::new(lines, filter, viewport);
+    let (width, height, max) = (
+        coverage.viewport.width(),
+        coverage.viewport.height(),
+        coverage.max().max(1),
+    );
+
+    let magic = match format {
+        ImageFormat::Pgm => "P5",
+        ImageFormat::Ppm => "P6",
+    };
+    let mut image = format!("{}\n{} {}\n255\n", magic, width, height).into_bytes();
+
+    for point in coverage.viewport.rows().flatten() {
+        let intensity = (coverage.at(point) * 255 / max) as u8;
+        match format {
+            ImageFormat::Pgm => image.push(intensity),
+            ImageFormat::Ppm => image.extend(heat(intensity)),
+        }
+    }
+    image
+}
+
+fn heat(intensity: u8) -> [u8; 3] {
+    let scaled = intensity as usize * 3;
+    let channel = |offset: usize| scaled.saturating_sub(offset).min(255) as u8;
+    [channel(0), channel(255), channel(510)]
+}
+
+#[test]
+fn example_diagrams() {
+    let lines = super::parse_input(include_str!("example.txt")).collect_vec();
+
+    assert_eq!(
+        render_text(&lines, LineFilter::AxisAligned, None),
+        "\
+.......1..
+..1....1..
+..1....1..
+.......1..
+.112111211
+..........
+..........
+..........
+..........
+222111....
+"
+    );
+
+    assert_eq!(
+        render_text(&lines, LineFilter::All, None),
+        "\
+1.1....11.
+.111...2..
+..2.1.111.
+...1.2.2..
+.112313211
+...1.2....
+..1...1...
+.1.....1..
+1.......1.
+222111....
+"
+    );
+}
+
+#[test]
+fn cropped_to_viewport() {
+    let lines = super::parse_input(include_str!("example.txt")).collect_vec();
+    let viewport = Region {
+        min: Point { x: 3, y: 3 },
+        max: Point { x: 7, y: 5 },
+    };
+
+    assert_eq!(
+        render_text(&lines, LineFilter::All, Some(viewport)),
+        "1.2.2\n23132\n1.2..\n"
+    );
+
+    let flipped = Region {
+        min: viewport.max,
+        max: viewport.min,
+    };
+    assert_eq!(
+        render_text(&lines, LineFilter::All, Some(flipped)),
+        "1.2.2\n23132\n1.2..\n"
+    );
+    assert_eq!(
+        render_image(&lines, LineFilter::All, Some(flipped), ImageFormat::Pgm).len(),
+        b"P5\n5 3\n255\n".len() + 15
+    );
+}
+
+#[test]
+fn netpbm_images() {
+    let lines = super::parse_input(include_str!("example.txt")).collect_vec();
+
+    let pgm = render_image(&lines, LineFilter::All, None, ImageFormat::Pgm);
+    let ppm = render_image(&lines, LineFilter::All, None, ImageFormat::Ppm);
+
+    assert!(pgm.starts_with(b"P5\n10 10\n255\n"));
+    assert_eq!(pgm.len(), b"P5\n10 10\n255\n".len() + 100);
+    assert!(ppm.starts_with(b"P6\n10 10\n255\n"));
+    assert_eq!(ppm.len(), b"P6\n10 10\n255\n".len() + 300);
+
+    // 4,4 is covered by three vents, the most anywhere, and 0,0 by one
+    let header = b"P5\n10 10\n255\n".len();
+    assert_eq!(pgm[header + 44], 255);
+    assert_eq!(pgm[header], 85);
+}
@@ -0,0 +1,269 @@
+use {
+    super::{Line, Point},
+    std::collections::HashMap,
+};
+
+/// The four families of lattice lines an octilinear vent can lie on, each described by
+/// `a * x + b * y = constant`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+enum Orientation {
+    Horizontal,
+    Vertical,
+    Rising,
+    Falling,
+}
+
+impl Orientation {
+    const ALL: [Orientation; 4] = [
+        Orientation::Horizontal,
+        Orientation::Vertical,
+        Orientation::Rising,
+        Orientation::Falling,
+    ];
+
+    fn of(line: &Line) -> Self {
+        let (dx, dy) = (line.stop.x - line.start.x, line.stop.y - line.start.y);
+        match (dx, dy) {
+            (_, 0) => Self::Horizontal,
+            (0, _) => Self::Vertical,
+            (dx, dy) if dx.signum() == dy.signum() => Self::Rising,
+            _ => Self::Falling,
+        }
+    }
+
+    fn coefficients(self) -> (i64, i64) {
+        match self {
+            Self::Horizontal => (0, 1),
+            Self::Vertical => (1, 0),
+            Self::Rising => (1, -1),
+            Self::Falling => (1, 1),
+        }
+    }
+
+    fn constant(self, point: Point) -> i64 {
+        let (a, b) = self.coefficients();
+        a * point.x as i64 + b * point.y as i64
+    }
+
+    /// Position of a point along its line: `y` for vertical lines and `x` for everything else.
+    fn position(self, point: Point) -> i64 {
+        match self {
+            Self::Vertical => point.y as i64,
+            _ => point.x as i64,
+        }
+    }
+
+    fn index(self) -> usize {
+        Self::ALL.iter().position(|&o| o == self).unwrap()
+    }
+}
+
+/// A run of lattice points along one line, all covered by the same number of vents.
+#[derive(Debug, Copy, Clone)]
+struct Piece {
+    orientation: Orientation,
+    constant: i64,
+    from: i64,
+    to: i64,
+    coverage: usize,
+}
+
+impl Piece {
+    fn len(&self) -> usize {
+        (self.to - self.from + 1) as usize
+    }
+
+    fn x_range(&self) -> (i64, i64) {
+        match self.orientation {
+            Orientation::Vertical => (self.constant, self.constant),
+            _ => (self.from, self.to),
+        }
+    }
+
+    fn intersection(&self, other: &Piece) -> Option<Point> {
+        let ((a1, b1), (a2, b2)) = (
+            self.orientation.coefficients(),
+            other.orientation.coefficients(),
+        );
+        let determinant = a1 * b2 - a2 * b1;
+        if determinant == 0 {
+            return None;
+        }
+
+        let (x, y) = (
+            self.constant * b2 - other.constant * b1,
+            a1 * other.constant - a2 * self.constant,
+        );
+        if x % determinant != 0 || y % determinant != 0 {
+            return None;
+        }
+
+        let point = Point {
+            x: (x / determinant) as i32,
+            y: (y / determinant) as i32,
+        };
+        (self.contains(point) && other.contains(point)).then_some(point)
+    }
+
+    fn contains(&self, point: Point) -> bool {
+        let position = self.orientation.position(point);
+        self.from <= position && position <= self.to
+    }
+}
+
+/// A vent the sweep can't handle because it isn't horizontal, vertical or at 45°.
+#[derive(Debug, Copy, Clone)]
+struct NotOctilinear(Line);
+
+/// Merges collinear vents into pieces of constant coverage with a 1D sweep along each line.
+fn pieces(lines: impl IntoIterator<Item = Line>) -> Result<Vec<Piece>, NotOctilinear> {
+    let mut events = HashMap::<(Orientation, i64), Vec<(i64, isize)>>::new();
+    for line in lines {
+        if !line.is_octilinear() {
+            return Err(NotOctilinear(line));
+        }
+
+        let orientation = Orientation::of(&line);
+        let (start, stop) = (
+            orientation.position(line.start),
+            orientation.position(line.stop),
+        );
+        let events = events
+            .entry((orientation, orientation.constant(line.start)))
+            .or_default();
+        events.push((start.min(stop), 1));
+        events.push((start.max(stop) + 1, -1));
+    }
+
+    let mut pieces = vec![];
+    for ((orientation, constant), mut events) in events {
+        events.sort_unstable();
+
+        let mut coverage = 0isize;
+        for pair in events.windows(2) {
+            let ((position, delta), (next, _)) = (pair[0], pair[1]);
+            coverage += delta;
+            if coverage > 0 && position < next {
+                pieces.push(Piece {
+                    orientation,
+                    constant,
+                    from: position,
+                    to: next - 1,
+                    coverage: coverage as usize,
+                });
+            }
+        }
+    }
+    Ok(pieces)
+}
+
+/// Coverage per orientation at every point where pieces of different orientations cross, found by
+/// sweeping across `x` and only testing pieces whose `x` ranges overlap.
+fn crossings(pieces: &[Piece]) -> HashMap<Point, [usize; 4]> {
+    let mut by_start = pieces.iter().collect::<Vec<_>>();
+    by_start.sort_unstable_by_key(|piece| piece.x_range().0);
+
+    let mut crossings = HashMap::<Point, [usize; 4]>::new();
+    let mut active = Vec::<&Piece>::new();
+    for piece in by_start {
+        let x = piece.x_range().0;
+        active.retain(|active| active.x_range().1 >= x);
+
+        for other in &active {
+            if let Some(point) = piece.intersection(other) {
+                let coverage = crossings.entry(point).or_default();
+                coverage[piece.orientation.index()] = piece.coverage;
+                coverage[other.orientation.index()] = other.coverage;
+            }
+        }
+        active.push(piece);
+    }
+    crossings
+}
+
+/// Number of points covered by at least `threshold` vents, without visiting each point.
+///
+/// Points on a single line are counted from piece lengths, then every crossing is corrected from
+/// its per-line coverage to the sum over all lines through it.
+///
+/// Fails on the first line that isn't octilinear, which would need rasterising instead.
+fn count_overlaps(
+    lines: impl IntoIterator<Item = Line>,
+    threshold: usize,
+) -> Result<usize, NotOctilinear> {
+    let pieces = pieces(lines)?;
+
+    let along_lines = pieces
+        .iter()
+        .filter(|piece| piece.coverage >= threshold)
+        .map(Piece::len)
+        .sum::<usize>();
+
+    Ok(crossings(&pieces)
+        .values()
+        .fold(along_lines, |count, coverage| {
+            let counted = coverage
+                .iter()
+                .filter(|&&c| c > 0 && c >= threshold)
+                .count();
+            let covered = coverage.iter().sum::<usize>() >= threshold;
+            count - counted + covered as usize
+        }))
+}
+
+#[test]
+fn example() {
+    let lines = || super::parse_input(include_str!("example.txt"));
+
+    assert_eq!(
+        count_overlaps(lines().filter(|line| line.direction().is_axis_aligned()), 2).unwrap(),
+        5
+    );
+    assert_eq!(count_overlaps(lines(), 2).unwrap(), 12);
+}
+
+#[test]
+fn solve() {
+    let lines = || super::parse_input(include_str!("input.txt"));
+
+    assert_eq!(
+        count_overlaps(lines().filter(|line| line.direction().is_axis_aligned()), 2).unwrap(),
+        6397
+    );
+    assert_eq!(count_overlaps(lines(), 2).unwrap(), 22335);
+}
+
+#[test]
+fn coordinates_in_the_millions() {
+    let lines = [
+        "0,0 -> 4000000,4000000",
+        "0,4000000 -> 4000000,0",
+        "1000000,1000000 -> 3000000,3000000",
+        "2000000,0 -> 2000000,4000000",
+    ]
+    .map(|line| line.parse::<Line>().unwrap());
+
+    assert_eq!(count_overlaps(lines, 2).unwrap(), 2000001);
+    assert_eq!(count_overlaps(lines, 3).unwrap(), 1);
+    assert_eq!(count_overlaps(lines, 4).unwrap(), 1);
+    assert_eq!(count_overlaps(lines, 5).unwrap(), 0);
+}
+
+#[test]
+fn zero_threshold_counts_covered_points() {
+    let lines = ["0,0 -> 0,0", "0,0 -> 0,1"].map(|line| line.parse::<Line>().unwrap());
+
+    assert_eq!(count_overlaps(lines, 0).unwrap(), 2);
+    assert_eq!(
+        super::VentField::new(lines).count_at_least(0, &super::Query::default()),
+        2
+    );
+}
+
+#[test]
+fn lines_at_other_angles_are_rejected() {
+    let lines = ["0,0 -> 4,4", "0,0 -> 5,2"].map(|line| line.parse::<Line>().unwrap());
+
+    let NotOctilinear(line) = count_overlaps(lines, 2).unwrap_err();
+    assert_eq!(line.to_string(), "0,0 -> 5,2");
+}
@@ -1,33 +1,329 @@
-use std::collections::VecDeque;
+use std::{collections::VecDeque, io};
 
-struct Population(VecDeque<u64>);
+/// The timer a fish resets to after spawning, and the timer a newborn starts on.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct Lifecycle {
+    reset: usize,
+    newborn: usize,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum LifecycleError {
+    ResetAfterNewborn,
+}
+
+impl Lifecycle {
+    fn new(reset: usize, newborn: usize) -> Result<Self, LifecycleError> {
+        if reset > newborn {
+            return Err(LifecycleError::ResetAfterNewborn);
+        }
+        Ok(Self { reset, newborn })
+    }
+
+    fn timers(self) -> usize {
+        self.newborn + 1
+    }
+
+    /// The factor the population eventually grows by each day: the root above one of
+    /// `1 = λ^-(reset + 1) + λ^-(newborn + 1)`, as every fish spawns once per `reset + 1` days and
+    /// newborns first spawn after `newborn + 1`.
+    fn growth_rate(self) -> f64 {
+        let excess = |rate: f64| {
+            rate.powi(-(self.reset as i32 + 1)) + rate.powi(-(self.newborn as i32 + 1)) - 1.0
+        };
+
+        let (mut low, mut high) = (1.0, 2.0);
+        for _ in 0..100 {
+            let mid = (low + high) / 2.0;
+            if excess(mid) > 0.0 {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+        (low + high) / 2.0
+    }
+}
+
+impl Default for Lifecycle {
+    fn default() -> Self {
+        Self {
+            reset: 6,
+            newborn: 8,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ParseError {
+    InvalidTimer(String),
+    TimerOutOfRange(usize),
+}
+
+struct Population {
+    timers: VecDeque<u64>,
+    lifecycle: Lifecycle,
+}
+
+fn parse_input(input: &str, lifecycle: Lifecycle) -> Result<Population, ParseError> {
+    let mut timers = VecDeque::from(vec![0; lifecycle.timers()]);
+    for timer in input.trim().split(',') {
+        let timer = timer
+            .trim()
+            .parse::<usize>()
+            .map_err(|_| ParseError::InvalidTimer(timer.to_string()))?;
+        *timers
+            .get_mut(timer)
+            .ok_or(ParseError::TimerOutOfRange(timer))? += 1;
+    }
 
-fn parse_input(input: &'static str) -> Population {
-    Population(
-        input
-            .split(',')
-            .map(|timer| timer.parse::<u64>().unwrap())
-            .fold(VecDeque::from([0; 9]), |mut population, time| {
-                population[time as usize] += 1;
-                population
-            }),
-    )
+    Ok(Population { timers, lifecycle })
 }
 
 impl Population {
     fn tick(&mut self) {
-        self.0.rotate_left(1);
-        self.0[6] += self.0[8];
+        self.timers.rotate_left(1);
+        self.timers[self.lifecycle.reset] += self.timers[self.lifecycle.newborn];
     }
 
     fn size(&self) -> u64 {
-        self.0.iter().sum()
+        self.timers.iter().sum()
+    }
+
+    /// Ticks through `days`, recording the timer histogram at the start and after every day.
+    fn series(&mut self, days: usize) -> TimeSeries {
+        let mut histograms = vec![self.timers.iter().copied().collect::<Vec<_>>()];
+        for _ in 0..days {
+            self.tick();
+            histograms.push(self.timers.iter().copied().collect());
+        }
+        TimeSeries(histograms)
+    }
+
+    /// The size after `days`, found by raising the daily transition matrix to the power of `days`
+    /// rather than ticking through every day.
+    ///
+    /// Fails if the size, or any power of the matrix needed along the way, doesn't fit in a `u128`.
+    fn after(&self, days: u64) -> Result<u128, Overflow> {
+        let timers = self
+            .timers
+            .iter()
+            .map(|&count| count as u128)
+            .collect::<Vec<_>>();
+        if timers.iter().all(|&count| count == 0) {
+            return Ok(0);
+        }
+
+        Matrix::transition(self.lifecycle)
+            .pow(days)?
+            .apply(&timers)?
+            .into_iter()
+            .try_fold(0u128, |size, count| size.checked_add(count).ok_or(Overflow))
+    }
+}
+
+/// Timer histograms indexed by day.
+struct TimeSeries(Vec<Vec<u64>>);
+
+impl TimeSeries {
+    fn sizes(&self) -> impl Iterator<Item = u64> + '_ {
+        self.0.iter().map(|timers| timers.iter().sum())
+    }
+
+    /// Day-on-day growth factors, skipping days when there are no fish.
+    fn growth(&self) -> impl Iterator<Item = f64> + '_ {
+        self.sizes()
+            .zip(self.sizes().skip(1))
+            .filter(|&(today, _)| today > 0)
+            .map(|(today, tomorrow)| tomorrow as f64 / today as f64)
+    }
+
+    /// One row per day: the day, the population size, then the count on each timer.
+    fn write_csv(&self, mut writer: impl io::Write) -> io::Result<()> {
+        let timers = self.0.first().map_or(0, Vec::len);
+        write!(writer, "day,size")?;
+        for timer in 0..timers {
+            write!(writer, ",timer_{}", timer)?;
+        }
+        writeln!(writer)?;
+
+        for (day, (histogram, size)) in self.0.iter().zip(self.sizes()).enumerate() {
+            write!(writer, "{},{}", day, size)?;
+            for count in histogram {
+                write!(writer, ",{}", count)?;
+            }
+            writeln!(writer)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct Overflow;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Matrix(Vec<Vec<u128>>);
+
+impl Matrix {
+    fn identity(n: usize) -> Self {
+        Self(
+            (0..n)
+                .map(|i| (0..n).map(|j| (i == j) as u128).collect())
+                .collect(),
+        )
     }
+
+    /// Maps today's timer counts to tomorrow's.
+    fn transition(lifecycle: Lifecycle) -> Self {
+        let mut matrix = Self(vec![vec![0; lifecycle.timers()]; lifecycle.timers()]);
+        for timer in 0..lifecycle.newborn {
+            matrix.0[timer][timer + 1] = 1;
+        }
+        matrix.0[lifecycle.newborn][0] += 1;
+        matrix.0[lifecycle.reset][0] += 1;
+        matrix
+    }
+
+    fn apply(&self, vector: &[u128]) -> Result<Vec<u128>, Overflow> {
+        self.0
+            .iter()
+            .map(|row| {
+                row.iter().zip(vector).try_fold(0u128, |sum, (&a, &b)| {
+                    a.checked_mul(b)
+                        .and_then(|product| sum.checked_add(product))
+                        .ok_or(Overflow)
+                })
+            })
+            .collect()
+    }
+
+    fn mul(&self, other: &Matrix) -> Result<Matrix, Overflow> {
+        let n = self.0.len();
+        let mut product = vec![vec![0u128; n]; n];
+        for (i, row) in product.iter_mut().enumerate() {
+            for (j, cell) in row.iter_mut().enumerate() {
+                *cell = (0..n).try_fold(0u128, |sum, k| {
+                    self.0[i][k]
+                        .checked_mul(other.0[k][j])
+                        .and_then(|term| sum.checked_add(term))
+                        .ok_or(Overflow)
+                })?;
+            }
+        }
+        Ok(Matrix(product))
+    }
+
+    fn pow(&self, mut exponent: u64) -> Result<Matrix, Overflow> {
+        let (mut result, mut base) = (Self::identity(self.0.len()), self.clone());
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = result.mul(&base)?;
+            }
+            exponent >>= 1;
+            if exponent > 0 {
+                base = base.mul(&base)?;
+            }
+        }
+        Ok(result)
+    }
+}
+
+#[test]
+fn example() {
+    let mut population = parse_input("3,4,3,1,2", Lifecycle::default()).unwrap();
+    for _ in 0..18 {
+        population.tick();
+    }
+    assert_eq!(population.size(), 26);
+}
+
+#[test]
+fn other_lifecycles() {
+    let lifecycle = Lifecycle::new(1, 2).unwrap();
+    let mut population = parse_input("0", lifecycle).unwrap();
+
+    let sizes = (0..6)
+        .map(|_| {
+            population.tick();
+            population.size()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(sizes, [2, 2, 3, 4, 5, 7]);
+
+    assert_eq!(Lifecycle::new(8, 6), Err(LifecycleError::ResetAfterNewborn));
+    assert_eq!(
+        parse_input("0,3", lifecycle).err(),
+        Some(ParseError::TimerOutOfRange(3))
+    );
+    assert_eq!(
+        parse_input("0,x", lifecycle).err(),
+        Some(ParseError::InvalidTimer("x".to_string()))
+    );
+}
+
+#[test]
+fn projection_matches_ticking() {
+    let mut population = parse_input(include_str!("input.txt"), Lifecycle::default()).unwrap();
+    let projected = (0..=100)
+        .map(|days| population.after(days).unwrap())
+        .collect::<Vec<_>>();
+
+    for expected in projected {
+        assert_eq!(population.size() as u128, expected);
+        population.tick();
+    }
+}
+
+#[test]
+fn projection_beyond_u64() {
+    let population = parse_input(include_str!("input.txt"), Lifecycle::default()).unwrap();
+
+    assert_eq!(population.after(256), Ok(1639854996917));
+    assert!(population.after(600).unwrap() > u64::MAX as u128);
+    assert_eq!(population.after(1_000_000_000), Err(Overflow));
+}
+
+#[test]
+fn example_series() {
+    let mut population = parse_input("3,4,3,1,2", Lifecycle::default()).unwrap();
+    let series = population.series(3);
+
+    assert_eq!(series.sizes().collect::<Vec<_>>(), [5, 5, 6, 7]);
+
+    let mut csv = vec![];
+    series.write_csv(&mut csv).unwrap();
+    assert_eq!(
+        String::from_utf8(csv).unwrap(),
+        "\
+day,size,timer_0,timer_1,timer_2,timer_3,timer_4,timer_5,timer_6,timer_7,timer_8
+0,5,0,1,1,2,1,0,0,0,0
+1,5,1,1,2,1,0,0,0,0,0
+2,6,1,2,1,0,0,0,1,0,1
+3,7,2,1,0,0,0,1,1,1,1
+"
+    );
+}
+
+#[test]
+fn growth_converges_to_asymptotic_rate() {
+    let lifecycle = Lifecycle::default();
+    let mut population = parse_input(include_str!("input.txt"), lifecycle).unwrap();
+
+    let rate = lifecycle.growth_rate();
+    assert!((rate - 1.0910).abs() < 1e-4);
+
+    // day-on-day growth still wobbles with the spawning cycle, so average over a week
+    let growth = population.series(256).growth().collect::<Vec<_>>();
+    let late_growth = growth[growth.len() - 7..]
+        .iter()
+        .product::<f64>()
+        .powf(1.0 / 7.0);
+    assert!((late_growth - rate).abs() < 1e-3);
 }
 
 #[test]
 fn solve_one() {
-    let mut population = parse_input(include_str!("input.txt"));
+    let mut population = parse_input(include_str!("input.txt"), Lifecycle::default()).unwrap();
     for _ in 0..80 {
         population.tick();
     }
@@ -36,7 +332,7 @@ fn solve_one() {
 
 #[test]
 fn solve_two() {
-    let mut population = parse_input(include_str!("input.txt"));
+    let mut population = parse_input(include_str!("input.txt"), Lifecycle::default()).unwrap();
     for _ in 0..256 {
         population.tick();
     }
@@ -1,10 +1,41 @@
-fn fuel_required_to_move_at_fixed_cost(start: i32, stop: i32) -> i32 {
-    (start - stop).abs()
+/// What it costs a crab, identified by its index in the input, to move between two positions.
+trait FuelModel {
+    fn fuel(&self, crab: usize, start: i32, stop: i32) -> u64;
 }
 
-fn fuel_required_to_move_at_increasing_cost(start: i32, stop: i32) -> i32 {
-    let n = fuel_required_to_move_at_fixed_cost(start, stop);
-    (n * (n + 1)) / 2
+/// Every step costs one unit of fuel.
+struct FixedCost;
+
+impl FuelModel for FixedCost {
+    fn fuel(&self, _: usize, start: i32, stop: i32) -> u64 {
+        start.abs_diff(stop) as u64
+    }
+}
+
+/// Each step costs one more than the last.
+struct IncreasingCost;
+
+impl FuelModel for IncreasingCost {
+    fn fuel(&self, crab: usize, start: i32, stop: i32) -> u64 {
+        let n = FixedCost.fuel(crab, start, stop);
+        (n * (n + 1)) / 2
+    }
+}
+
+/// Any cost depending only on where the crab starts and stops.
+impl<F: Fn(i32, i32) -> u64> FuelModel for F {
+    fn fuel(&self, _: usize, start: i32, stop: i32) -> u64 {
+        self(start, stop)
+    }
+}
+
+/// A cost that also depends on which crab is moving, e.g. to give each crab its own efficiency.
+struct PerCrab<F>(F);
+
+impl<F: Fn(usize, i32, i32) -> u64> FuelModel for PerCrab<F> {
+    fn fuel(&self, crab: usize, start: i32, stop: i32) -> u64 {
+        (self.0)(crab, start, stop)
+    }
 }
 
 fn crab_positions_range(
@@ -17,48 +48,307 @@ fn crab_positions_range(
     min..=max
 }
 
+/// Saturates at `u64::MAX` rather than wrapping, so an overflowing total never looks cheapest.
 fn amount_of_fuel_required_to_move_crabs_to_position(
     crabs: impl Iterator<Item = i32>,
     destination: i32,
-    fuel_calculation: fn(i32, i32) -> i32,
-) -> i32 {
+    fuel_model: &impl FuelModel,
+) -> u64 {
     crabs
-        .map(|crab_position| fuel_calculation(crab_position, destination))
-        .sum()
+        .enumerate()
+        .map(|(crab, crab_position)| fuel_model.fuel(crab, crab_position, destination))
+        .fold(0, u64::saturating_add)
 }
 
-fn min_amount_of_fuel_to_align_the_crabs(fuel_calculation: fn(i32, i32) -> i32) -> i32 {
-    let crab_positions = get_crab_positions();
-    crab_positions_range(crab_positions.clone())
+fn min_amount_of_fuel_to_align_the_crabs(
+    crab_positions: &[i32],
+    fuel_model: &impl FuelModel,
+) -> u64 {
+    crab_positions_range(crab_positions.iter().copied())
         .map(|position| {
             amount_of_fuel_required_to_move_crabs_to_position(
-                crab_positions.clone(),
+                crab_positions.iter().copied(),
                 position,
-                fuel_calculation,
+                fuel_model,
             )
         })
         .min()
         .unwrap()
 }
 
-fn get_crab_positions() -> impl Iterator<Item = i32> + Clone {
-    include_str!("input.txt")
+/// Total fixed-cost fuel is minimised at the median, since moving away from it takes every crab on
+/// the far side one step further for each step only the others save.
+fn min_fuel_at_fixed_cost(crab_positions: &[i32]) -> u64 {
+    let mut sorted = crab_positions.to_vec();
+    let middle = sorted.len() / 2;
+    let (_, &mut median, _) = sorted.select_nth_unstable(middle);
+
+    amount_of_fuel_required_to_move_crabs_to_position(
+        crab_positions.iter().copied(),
+        median,
+        &FixedCost,
+    )
+}
+
+/// Total increasing-cost fuel is `Σ (d² + |d|) / 2`, whose real minimum lies within half a step of
+/// the mean, so only the integers around it need trying.
+fn min_fuel_at_increasing_cost(crab_positions: &[i32]) -> u64 {
+    let sum = crab_positions
+        .iter()
+        .map(|&position| position as i64)
+        .sum::<i64>();
+    let mean = sum.div_euclid(crab_positions.len() as i64) as i32;
+
+    (mean - 1..=mean + 1)
+        .map(|position| {
+            amount_of_fuel_required_to_move_crabs_to_position(
+                crab_positions.iter().copied(),
+                position,
+                &IncreasingCost,
+            )
+        })
+        .min()
+        .unwrap()
+}
+
+/// Works for any cost that is convex in the distance moved, as the total is then convex in the
+/// destination: binary search for where moving one step right stops helping.
+fn min_fuel_by_convex_search(crab_positions: &[i32], fuel_model: &impl FuelModel) -> u64 {
+    amount_of_fuel_required_to_move_crabs_to_position(
+        crab_positions.iter().copied(),
+        optimal_position_by_convex_search(crab_positions, fuel_model),
+        fuel_model,
+    )
+}
+
+fn optimal_position_by_convex_search(crab_positions: &[i32], fuel_model: &impl FuelModel) -> i32 {
+    let fuel = |position| {
+        amount_of_fuel_required_to_move_crabs_to_position(
+            crab_positions.iter().copied(),
+            position,
+            fuel_model,
+        )
+    };
+
+    let (mut low, mut high) = (
+        *crab_positions.iter().min().unwrap(),
+        *crab_positions.iter().max().unwrap(),
+    );
+    while low < high {
+        let middle = low + (high - low) / 2;
+        if fuel(middle) <= fuel(middle + 1) {
+            high = middle;
+        } else {
+            low = middle + 1;
+        }
+    }
+    low
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Alignment {
+    /// Every position that needs the least fuel, in ascending order.
+    positions: Vec<i32>,
+    fuel: u64,
+    /// What each crab, in input order, spends reaching the first of `positions`.
+    crab_costs: Vec<u64>,
+}
+
+impl Alignment {
+    /// Widens the leftmost optimum to all the positions tied with it, which are adjacent because
+    /// the total is convex. A convex total always has an optimum among the crabs, so the search
+    /// stops at the rightmost crab even if the cost stays flat beyond it.
+    fn around(crab_positions: &[i32], first: i32, fuel_model: &impl FuelModel) -> Self {
+        let fuel = |position| {
+            amount_of_fuel_required_to_move_crabs_to_position(
+                crab_positions.iter().copied(),
+                position,
+                fuel_model,
+            )
+        };
+        let least_fuel = fuel(first);
+
+        let rightmost_crab = crab_positions.iter().copied().max().unwrap_or(first);
+        let last = (first + 1..=rightmost_crab)
+            .take_while(|&position| fuel(position) == least_fuel)
+            .last()
+            .unwrap_or(first);
+
+        Self {
+            positions: (first..=last).collect(),
+            fuel: least_fuel,
+            crab_costs: crab_positions
+                .iter()
+                .enumerate()
+                .map(|(crab, &crab_position)| fuel_model.fuel(crab, crab_position, first))
+                .collect(),
+        }
+    }
+
+    /// The crabs that spend the most fuel, as indices into the input, most expensive first.
+    fn costliest_crabs(&self) -> impl Iterator<Item = usize> + '_ {
+        let mut crabs = (0..self.crab_costs.len()).collect::<Vec<_>>();
+        crabs.sort_by_key(|&crab| std::cmp::Reverse(self.crab_costs[crab]));
+        crabs.into_iter()
+    }
+}
+
+fn align_the_crabs(crab_positions: &[i32], fuel_model: &impl FuelModel) -> Alignment {
+    Alignment::around(
+        crab_positions,
+        optimal_position_by_convex_search(crab_positions, fuel_model),
+        fuel_model,
+    )
+}
+
+fn parse_input(input: &str) -> Vec<i32> {
+    input
+        .trim()
         .split(',')
-        .map(|position| position.parse().unwrap())
+        .map(|position| position.trim().parse().unwrap())
+        .collect()
+}
+
+#[test]
+fn example() {
+    let crab_positions = parse_input(include_str!("example.txt"));
+
+    assert_eq!(
+        min_amount_of_fuel_to_align_the_crabs(&crab_positions, &FixedCost),
+        37
+    );
+    assert_eq!(
+        min_amount_of_fuel_to_align_the_crabs(&crab_positions, &IncreasingCost),
+        168
+    );
 }
 
 #[test]
 fn part_one() {
+    let crab_positions = parse_input(include_str!("input.txt"));
+
     assert_eq!(
-        min_amount_of_fuel_to_align_the_crabs(fuel_required_to_move_at_fixed_cost),
+        min_amount_of_fuel_to_align_the_crabs(&crab_positions, &FixedCost),
         344735
     );
 }
 
 #[test]
 fn part_two() {
+    let crab_positions = parse_input(include_str!("input.txt"));
+
     assert_eq!(
-        min_amount_of_fuel_to_align_the_crabs(fuel_required_to_move_at_increasing_cost),
+        min_amount_of_fuel_to_align_the_crabs(&crab_positions, &IncreasingCost),
         96798233
     );
 }
+
+#[test]
+fn parses_padded_positions() {
+    assert_eq!(parse_input(" 16, 1,2 ,\t0,4,\n2\n\n"), [16, 1, 2, 0, 4, 2]);
+}
+
+#[test]
+fn fast_solvers() {
+    let crab_positions = parse_input(include_str!("input.txt"));
+
+    assert_eq!(min_fuel_at_fixed_cost(&crab_positions), 344735);
+    assert_eq!(min_fuel_at_increasing_cost(&crab_positions), 96798233);
+    assert_eq!(
+        min_fuel_by_convex_search(&crab_positions, &FixedCost),
+        344735
+    );
+    assert_eq!(
+        min_fuel_by_convex_search(&crab_positions, &IncreasingCost),
+        96798233
+    );
+}
+
+#[test]
+fn fast_solvers_on_example() {
+    let crab_positions = parse_input(include_str!("example.txt"));
+
+    assert_eq!(min_fuel_at_fixed_cost(&crab_positions), 37);
+    assert_eq!(min_fuel_at_increasing_cost(&crab_positions), 168);
+    assert_eq!(
+        min_fuel_by_convex_search(&crab_positions, &|start: i32, stop: i32| {
+            (start.abs_diff(stop) as u64).pow(2)
+        }),
+        291
+    );
+}
+
+#[test]
+fn alignment_on_example() {
+    let crab_positions = parse_input(include_str!("example.txt"));
+
+    assert_eq!(
+        align_the_crabs(&crab_positions, &FixedCost),
+        Alignment {
+            positions: vec![2],
+            fuel: 37,
+            crab_costs: vec![14, 1, 0, 2, 2, 0, 5, 1, 0, 12],
+        }
+    );
+
+    let alignment = align_the_crabs(&crab_positions, &IncreasingCost);
+    assert_eq!(alignment.positions, [5]);
+    assert_eq!(alignment.fuel, 168);
+    assert_eq!(alignment.crab_costs.iter().sum::<u64>(), 168);
+    assert_eq!(
+        alignment.costliest_crabs().take(2).collect::<Vec<_>>(),
+        [0, 9]
+    );
+}
+
+#[test]
+fn alignment_reports_ties() {
+    let alignment = align_the_crabs(&[0, 10], &FixedCost);
+
+    assert_eq!(alignment.positions, (0..=10).collect::<Vec<_>>());
+    assert_eq!(alignment.fuel, 10);
+    assert_eq!(alignment.crab_costs, [0, 10]);
+
+    let free = align_the_crabs(&[3, 1, 4], &|_, _| 0);
+    assert_eq!(free.positions, [1, 2, 3, 4]);
+    assert_eq!(free.fuel, 0);
+}
+
+#[test]
+fn alignment_on_input() {
+    let crab_positions = parse_input(include_str!("input.txt"));
+
+    assert_eq!(align_the_crabs(&crab_positions, &FixedCost).fuel, 344735);
+    assert_eq!(
+        align_the_crabs(&crab_positions, &IncreasingCost).fuel,
+        96798233
+    );
+}
+
+#[test]
+fn custom_fuel_models() {
+    let crab_positions = parse_input(include_str!("example.txt"));
+
+    let exponential = |start: i32, stop: i32| {
+        1u64.checked_shl(start.abs_diff(stop))
+            .map_or(u64::MAX, |cost| cost - 1)
+    };
+    assert_eq!(
+        align_the_crabs(&crab_positions, &exponential).positions,
+        [7]
+    );
+
+    let capped = |start: i32, stop: i32| (start.abs_diff(stop) as u64).min(3);
+    assert_eq!(
+        min_amount_of_fuel_to_align_the_crabs(&crab_positions, &capped),
+        15
+    );
+
+    let sluggish_outliers = PerCrab(|crab, start: i32, stop: i32| {
+        let efficiency = if [0, 9].contains(&crab) { 10 } else { 1 };
+        efficiency * start.abs_diff(stop) as u64
+    });
+    let alignment = align_the_crabs(&crab_positions, &sluggish_outliers);
+    assert_eq!(alignment.positions, [14]);
+    assert_eq!(alignment.costliest_crabs().next(), Some(0));
+}
@@ -1,12 +1,51 @@
+mod render;
+
 use {
+    crate::digit_grid::{self, DigitGrid, ParseError},
     itertools::Itertools,
-    std::{collections::HashSet, str::FromStr},
+    std::{
+        collections::{HashMap, HashSet},
+        str::FromStr,
+    },
 };
 
 #[derive(Debug)]
 struct HeightMap {
     heights: Vec<u32>,
-    row_length: usize,
+    width: usize,
+    height: usize,
+    neighbourhood: Neighbourhood,
+    /// Cells this high or higher are walls: never low points, and never part of a basin.
+    wall_height: u32,
+}
+
+/// Which cells count as adjacent, as offsets from the cell in question.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Neighbourhood {
+    /// The four orthogonal neighbours.
+    VonNeumann,
+    /// The eight orthogonal and diagonal neighbours.
+    Moore,
+    Custom(Vec<(i32, i32)>),
+}
+
+impl Neighbourhood {
+    fn offsets(&self) -> &[(i32, i32)] {
+        match self {
+            Self::VonNeumann => &[(0, -1), (0, 1), (-1, 0), (1, 0)],
+            Self::Moore => &[
+                (0, -1),
+                (0, 1),
+                (-1, 0),
+                (1, 0),
+                (-1, -1),
+                (1, -1),
+                (-1, 1),
+                (1, 1),
+            ],
+            Self::Custom(offsets) => offsets,
+        }
+    }
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
@@ -16,24 +55,40 @@ struct Coordinate {
 }
 
 impl FromStr for HeightMap {
-    type Err = ();
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let DigitGrid {
+            digits: heights,
+            width,
+            height,
+        } = digit_grid::parse(s)?;
+
         Ok(HeightMap {
-            heights: s.lines().fold(vec![], |mut height_map, heights| {
-                let mut row = heights
-                    .chars()
-                    .map(|char| char.to_digit(10).unwrap())
-                    .collect::<Vec<_>>();
-                height_map.append(&mut row);
-                height_map
-            }),
-            row_length: s.lines().next().unwrap().len(),
+            heights,
+            width,
+            height,
+            neighbourhood: Neighbourhood::VonNeumann,
+            wall_height: 9,
         })
     }
 }
 
 impl HeightMap {
+    fn with_neighbourhood(self, neighbourhood: Neighbourhood) -> Self {
+        Self {
+            neighbourhood,
+            ..self
+        }
+    }
+
+    fn with_wall_height(self, wall_height: u32) -> Self {
+        Self {
+            wall_height,
+            ..self
+        }
+    }
+
     fn get_height_at(&self, coordinate: Coordinate) -> u32 {
         self.get_index_for_coordinate(coordinate)
             .and_then(|index| self.heights.get(index).cloned())
@@ -42,16 +97,16 @@ impl HeightMap {
 
     fn get_coordinate_for_index(&self, index: usize) -> Option<Coordinate> {
         (index < self.heights.len()).then(|| Coordinate {
-            x: (index % self.row_length) as i32,
-            y: (index / self.row_length) as i32,
+            x: (index % self.width) as i32,
+            y: (index / self.width) as i32,
         })
     }
 
     fn coordinate_in_bounds(&self, coordinate: Coordinate) -> bool {
         coordinate.x >= 0
-            && coordinate.x < self.row_length as i32
+            && coordinate.x < self.width as i32
             && coordinate.y >= 0
-            && coordinate.y < self.row_length as i32
+            && coordinate.y < self.height as i32
     }
 
     fn get_index_for_coordinate(&self, coordinate: Coordinate) -> Option<usize> {
@@ -59,7 +114,7 @@ impl HeightMap {
             return None;
         }
 
-        let index = (coordinate.y * self.row_length as i32) + coordinate.x;
+        let index = (coordinate.y * self.width as i32) + coordinate.x;
         (index >= 0 && index < self.heights.len() as i32).then(|| index as usize)
     }
 
@@ -71,7 +126,8 @@ impl HeightMap {
         &self,
         coordinate: Coordinate,
     ) -> impl Iterator<Item = Coordinate> + '_ {
-        [(0, -1), (0, 1), (-1, 0), (1, 0)]
+        self.neighbourhood
+            .offsets()
             .iter()
             .map(move |(x, y)| Coordinate {
                 x: coordinate.x + x,
@@ -83,9 +139,11 @@ impl HeightMap {
     fn is_low_point(&self, coordinate: Coordinate) -> bool {
         let height_at_coordinate = self.get_height_at(coordinate);
 
-        self.adjacent_coordinates(coordinate)
-            .map(|adjacent| self.get_height_at(adjacent))
-            .all(|adjacent_height| height_at_coordinate < adjacent_height)
+        height_at_coordinate < self.wall_height
+            && self
+                .adjacent_coordinates(coordinate)
+                .map(|adjacent| self.get_height_at(adjacent))
+                .all(|adjacent_height| height_at_coordinate < adjacent_height)
     }
 
     fn low_points(&self) -> impl Iterator<Item = Coordinate> + '_ {
@@ -117,7 +175,9 @@ impl HeightMap {
             .map(|adjacent_coordinate| {
                 (adjacent_coordinate, self.get_height_at(adjacent_coordinate))
             })
-            .filter(|&(_, height_at_adjacent_coordinate)| height_at_adjacent_coordinate < 9)
+            .filter(|&(_, height_at_adjacent_coordinate)| {
+                height_at_adjacent_coordinate < self.wall_height
+            })
             .filter(move |&(_, height_at_adjacent_coordinate)| {
                 height_at_adjacent_coordinate > height_at_coordinate
             })
@@ -125,26 +185,428 @@ impl HeightMap {
     }
 }
 
+/// Disjoint sets over cell indices, with path halving and union by size.
+struct DisjointSets {
+    parents: Vec<usize>,
+    sizes: Vec<usize>,
+}
+
+impl DisjointSets {
+    fn new(n: usize) -> Self {
+        Self {
+            parents: (0..n).collect(),
+            sizes: vec![1; n],
+        }
+    }
+
+    fn find(&mut self, mut i: usize) -> usize {
+        while self.parents[i] != i {
+            self.parents[i] = self.parents[self.parents[i]];
+            i = self.parents[i];
+        }
+        i
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b {
+            return;
+        }
+        let (larger, smaller) = if self.sizes[a] >= self.sizes[b] {
+            (a, b)
+        } else {
+            (b, a)
+        };
+        self.parents[smaller] = larger;
+        self.sizes[larger] += self.sizes[smaller];
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Basin {
+    size: usize,
+    /// How far below the walls the basin's floor lies, averaged over its cells.
+    mean_depth: f64,
+    low_point: Coordinate,
+}
+
+/// Every cell's basin, as an index into `basins`, or `None` for walls.
+struct Basins {
+    labels: Vec<Option<usize>>,
+    basins: Vec<Basin>,
+}
+
+impl Basins {
+    fn label_at(&self, height_map: &HeightMap, coordinate: Coordinate) -> Option<usize> {
+        height_map
+            .get_index_for_coordinate(coordinate)
+            .and_then(|index| self.labels[index])
+    }
+}
+
+impl HeightMap {
+    /// Labels the whole map in one pass by joining every non-wall cell with its non-wall
+    /// neighbours, then numbers the resulting sets in the order their first cell appears.
+    fn basins(&self) -> Basins {
+        let mut sets = DisjointSets::new(self.heights.len());
+        for (index, coordinate) in self.coordinates().enumerate() {
+            if self.heights[index] >= self.wall_height {
+                continue;
+            }
+            for adjacent in self.adjacent_coordinates(coordinate) {
+                let adjacent = self.get_index_for_coordinate(adjacent).unwrap();
+                if self.heights[adjacent] < self.wall_height {
+                    sets.union(index, adjacent);
+                }
+            }
+        }
+
+        let mut labels = vec![None; self.heights.len()];
+        let mut label_of_root = HashMap::new();
+        let mut basins = Vec::<Basin>::new();
+        let mut total_depths = Vec::<u32>::new();
+        for (index, coordinate) in self.coordinates().enumerate() {
+            let height = self.heights[index];
+            if height >= self.wall_height {
+                continue;
+            }
+
+            let label = *label_of_root.entry(sets.find(index)).or_insert_with(|| {
+                basins.push(Basin {
+                    size: 0,
+                    mean_depth: 0.0,
+                    low_point: coordinate,
+                });
+                total_depths.push(0);
+                basins.len() - 1
+            });
+            labels[index] = Some(label);
+
+            let basin = &mut basins[label];
+            basin.size += 1;
+            total_depths[label] += self.wall_height - height;
+            if height < self.get_height_at(basin.low_point) {
+                basin.low_point = coordinate;
+            }
+        }
+
+        for (basin, total_depth) in basins.iter_mut().zip(total_depths) {
+            basin.mean_depth = total_depth as f64 / basin.size as f64;
+        }
+
+        Basins { labels, basins }
+    }
+}
+
+/// Which way water goes when several neighbours are equally the steepest way down.
+#[derive(Copy, Clone)]
+enum TieBreak {
+    /// The neighbour that comes first reading row by row.
+    ReadingOrder,
+    /// The neighbour that comes last reading row by row.
+    ReverseReadingOrder,
+    /// Picks one of the candidates, which are given in reading order.
+    Custom(fn(&[Coordinate]) -> Coordinate),
+}
+
+impl TieBreak {
+    fn choose(self, candidates: &[Coordinate]) -> Coordinate {
+        match self {
+            Self::ReadingOrder => candidates[0],
+            Self::ReverseReadingOrder => candidates[candidates.len() - 1],
+            Self::Custom(choose) => choose(candidates),
+        }
+    }
+}
+
+/// Where water flows from every cell, always moving to the lowest strictly lower neighbour until it
+/// reaches a sink, a cell with no lower neighbour.
+struct Drainage<'a> {
+    height_map: &'a HeightMap,
+    downhill: Vec<Option<Coordinate>>,
+    sinks: Vec<Coordinate>,
+    /// Every sink reachable by some choice at each tie, in reading order.
+    reachable_sinks: Vec<Vec<Coordinate>>,
+}
+
+impl HeightMap {
+    fn drainage(&self, tie_break: TieBreak) -> Drainage<'_> {
+        let mut downhill = vec![None; self.heights.len()];
+        let mut sinks = self.coordinates().collect_vec();
+        let mut reachable_sinks = vec![vec![]; self.heights.len()];
+
+        // lower cells first, so everything a cell can drain into is already resolved
+        let by_height = self
+            .coordinates()
+            .enumerate()
+            .sorted_by_key(|&(index, _)| self.heights[index]);
+        for (index, coordinate) in by_height {
+            let height = self.heights[index];
+            let lower = self
+                .adjacent_coordinates(coordinate)
+                .filter(|&adjacent| self.get_height_at(adjacent) < height)
+                .collect_vec();
+            let lowest = lower
+                .iter()
+                .map(|&adjacent| self.get_height_at(adjacent))
+                .min()
+                .map(|lowest_height| {
+                    lower
+                        .iter()
+                        .copied()
+                        .filter(|&adjacent| self.get_height_at(adjacent) == lowest_height)
+                        .sorted_by_key(|adjacent| (adjacent.y, adjacent.x))
+                        .collect_vec()
+                })
+                .unwrap_or_default();
+
+            if lowest.is_empty() {
+                reachable_sinks[index] = vec![coordinate];
+                continue;
+            }
+
+            let next = tie_break.choose(&lowest);
+            let next_index = self.get_index_for_coordinate(next).unwrap();
+            downhill[index] = Some(next);
+            sinks[index] = sinks[next_index];
+            reachable_sinks[index] = lowest
+                .iter()
+                .flat_map(|&adjacent| {
+                    reachable_sinks[self.get_index_for_coordinate(adjacent).unwrap()].clone()
+                })
+                .sorted_by_key(|sink| (sink.y, sink.x))
+                .dedup()
+                .collect();
+        }
+
+        Drainage {
+            height_map: self,
+            downhill,
+            sinks,
+            reachable_sinks,
+        }
+    }
+}
+
+impl Drainage<'_> {
+    fn index(&self, coordinate: Coordinate) -> usize {
+        self.height_map
+            .get_index_for_coordinate(coordinate)
+            .expect("not a valid coordinate")
+    }
+
+    fn drains_to(&self, coordinate: Coordinate) -> Coordinate {
+        self.sinks[self.index(coordinate)]
+    }
+
+    /// The route of steepest descent from `coordinate` to its sink, both included.
+    fn path(&self, coordinate: Coordinate) -> impl Iterator<Item = Coordinate> + '_ {
+        std::iter::successors(Some(coordinate), move |&coordinate| {
+            self.downhill[self.index(coordinate)]
+        })
+    }
+
+    fn reachable_sinks(&self, coordinate: Coordinate) -> &[Coordinate] {
+        &self.reachable_sinks[self.index(coordinate)]
+    }
+
+    /// Cells on a divide: depending on how ties are broken they drain to different sinks.
+    fn divides(&self) -> impl Iterator<Item = Coordinate> + '_ {
+        self.height_map
+            .coordinates()
+            .filter(|&coordinate| self.reachable_sinks(coordinate).len() > 1)
+    }
+}
+
+fn sum_of_low_point_risk_levels(height_map: &HeightMap) -> u32 {
+    height_map
+        .low_points()
+        .map(|coordinate| height_map.get_height_at(coordinate) + 1)
+        .sum()
+}
+
+fn product_of_three_largest_basins(height_map: &HeightMap) -> usize {
+    height_map
+        .basins()
+        .basins
+        .iter()
+        .map(|basin| basin.size)
+        .sorted()
+        .rev()
+        .take(3)
+        .product()
+}
+
+#[test]
+fn wide_and_tall_maps() {
+    let wide = include_str!("example.txt").parse::<HeightMap>().unwrap();
+    let tall = digit_grid::transpose(include_str!("example.txt"))
+        .parse::<HeightMap>()
+        .unwrap();
+
+    assert_eq!((wide.width, wide.height), (10, 5));
+    assert_eq!((tall.width, tall.height), (5, 10));
+
+    for height_map in [wide, tall] {
+        assert!(!height_map.coordinate_in_bounds(Coordinate {
+            x: height_map.width as i32,
+            y: 0
+        }));
+        assert!(!height_map.coordinate_in_bounds(Coordinate {
+            x: 0,
+            y: height_map.height as i32
+        }));
+        assert_eq!(sum_of_low_point_risk_levels(&height_map), 15);
+        assert_eq!(product_of_three_largest_basins(&height_map), 1134);
+    }
+}
+
+#[test]
+fn labelled_basins_match_upward_flows() {
+    let height_map = include_str!("input.txt").parse::<HeightMap>().unwrap();
+    let basins = height_map.basins();
+
+    let low_points = height_map.low_points().collect::<HashSet<_>>();
+    assert_eq!(basins.basins.len(), low_points.len());
+
+    for basin in &basins.basins {
+        assert!(low_points.contains(&basin.low_point));
+        assert_eq!(height_map.basin(basin.low_point).len(), basin.size);
+    }
+}
+
+#[test]
+fn example_basins() {
+    let height_map = include_str!("example.txt").parse::<HeightMap>().unwrap();
+    let basins = height_map.basins();
+
+    assert_eq!(
+        basins.basins.iter().map(|basin| basin.size).collect_vec(),
+        [3, 9, 14, 9]
+    );
+    assert_eq!(
+        basins.basins[0],
+        Basin {
+            size: 3,
+            mean_depth: 7.0,
+            low_point: Coordinate { x: 1, y: 0 },
+        }
+    );
+    assert_eq!(
+        basins.label_at(&height_map, Coordinate { x: 0, y: 0 }),
+        Some(0)
+    );
+    assert_eq!(
+        basins.label_at(&height_map, Coordinate { x: 2, y: 0 }),
+        None
+    );
+    assert_eq!(
+        basins.label_at(&height_map, Coordinate { x: 9, y: 0 }),
+        Some(1)
+    );
+}
+
+#[test]
+fn drainage_tie_breaking() {
+    let height_map = "010\n999".parse::<HeightMap>().unwrap();
+    let middle = Coordinate { x: 1, y: 0 };
+    let (left, right) = (Coordinate { x: 0, y: 0 }, Coordinate { x: 2, y: 0 });
+
+    let drainage = height_map.drainage(TieBreak::ReadingOrder);
+    assert_eq!(drainage.path(middle).collect_vec(), [middle, left]);
+    assert_eq!(drainage.reachable_sinks(middle), [left, right]);
+    assert_eq!(
+        drainage.divides().collect_vec(),
+        [middle, Coordinate { x: 1, y: 1 }]
+    );
+
+    let drainage = height_map.drainage(TieBreak::ReverseReadingOrder);
+    assert_eq!(drainage.drains_to(middle), right);
+    assert_eq!(drainage.drains_to(Coordinate { x: 0, y: 1 }), left);
+
+    let drainage = height_map.drainage(TieBreak::Custom(|candidates| candidates[0]));
+    assert_eq!(drainage.drains_to(middle), left);
+}
+
+#[test]
+fn drainage_follows_steepest_descent() {
+    let height_map = include_str!("input.txt").parse::<HeightMap>().unwrap();
+    let drainage = height_map.drainage(TieBreak::ReadingOrder);
+
+    let low_points = height_map.low_points().collect::<HashSet<_>>();
+    for coordinate in height_map.coordinates() {
+        let heights = drainage
+            .path(coordinate)
+            .map(|step| height_map.get_height_at(step))
+            .collect_vec();
+        assert!(heights.windows(2).all(|pair| pair[0] > pair[1]));
+
+        let sink = drainage.drains_to(coordinate);
+        assert_eq!(drainage.path(coordinate).last(), Some(sink));
+        assert!(drainage.reachable_sinks(coordinate).contains(&sink));
+        if low_points.contains(&coordinate) {
+            assert_eq!(sink, coordinate);
+        }
+    }
+}
+
+#[test]
+fn configurable_neighbourhood_and_walls() {
+    let example = || include_str!("example.txt").parse::<HeightMap>().unwrap();
+    let basin_sizes = |height_map: &HeightMap| {
+        height_map
+            .basins()
+            .basins
+            .iter()
+            .map(|basin| basin.size)
+            .collect_vec()
+    };
+
+    let moore = example().with_neighbourhood(Neighbourhood::Moore);
+    assert_eq!(
+        moore.low_points().collect_vec(),
+        [
+            Coordinate { x: 1, y: 0 },
+            Coordinate { x: 9, y: 0 },
+            Coordinate { x: 2, y: 2 },
+            Coordinate { x: 6, y: 4 }
+        ]
+    );
+    assert_eq!(basin_sizes(&moore), [35]);
+
+    let rows_only = example().with_neighbourhood(Neighbourhood::Custom(vec![(-1, 0), (1, 0)]));
+    assert_eq!(
+        rows_only
+            .adjacent_coordinates(Coordinate { x: 0, y: 0 })
+            .count(),
+        1
+    );
+    assert_eq!(
+        rows_only.basin(Coordinate { x: 1, y: 0 }).len(),
+        basin_sizes(&rows_only)[0]
+    );
+
+    let low_walls = example().with_wall_height(8);
+    assert_eq!(basin_sizes(&low_walls), [3, 9, 7, 6]);
+    assert!(low_walls.low_points().all(|low_point| {
+        low_walls.get_height_at(low_point) < 8
+            && low_walls
+                .basin(low_point)
+                .iter()
+                .all(|&cell| low_walls.get_height_at(cell) < 8)
+    }));
+}
+
 #[test]
 fn part_one() {
     let height_map = include_str!("input.txt").parse::<HeightMap>().unwrap();
-    let risk_level = |coordinate| height_map.get_height_at(coordinate) + 1;
 
-    let sum_of_low_point_risk_levels = height_map.low_points().map(risk_level).sum::<u32>();
-    assert_eq!(sum_of_low_point_risk_levels, 600);
+    assert_eq!(sum_of_low_point_risk_levels(&height_map), 600);
 }
 
 #[test]
 fn part_two() {
     let height_map = include_str!("input.txt").parse::<HeightMap>().unwrap();
 
-    let product_of_three_largest_basins = height_map
-        .low_points()
-        .map(|low_point| height_map.basin(low_point).len())
-        .sorted()
-        .rev()
-        .take(3)
-        .product::<usize>();
-
-    assert_eq!(product_of_three_largest_basins, 987840);
+    assert_eq!(product_of_three_largest_basins(&height_map), 987840);
 }
@@ -0,0 +1,188 @@
+use {
+    super::{Basins, Coordinate, HeightMap},
+    itertools::Itertools,
+    std::io,
+};
+
+type Rgb = [u8; 3];
+
+const WALL: Rgb = [40, 40, 40];
+const LOW_POINT: Rgb = [255, 255, 255];
+
+/// A distinct colour for each basin, stepping round the hue circle by the golden angle so
+/// neighbouring labels never look alike.
+fn basin_colour(label: usize) -> Rgb {
+    let hue = (label as f64 * 137.507_764) % 360.0;
+    let (saturation, value) = (0.65, 0.9);
+
+    let chroma = value * saturation;
+    let x = chroma * (1.0 - ((hue / 60.0) % 2.0 - 1.0).abs());
+    let (r, g, b) = match (hue / 60.0) as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = value - chroma;
+    [r, g, b].map(|channel| ((channel + m) * 255.0).round() as u8)
+}
+
+/// Darkens a basin's colour towards its rim, so the floor stands out.
+fn shade(colour: Rgb, height: u32, wall_height: u32) -> Rgb {
+    let depth = wall_height.saturating_sub(height) as f64 / wall_height.max(1) as f64;
+    colour.map(|channel| (channel as f64 * (0.4 + 0.6 * depth)) as u8)
+}
+
+fn cell_colour(height_map: &HeightMap, basins: &Basins, coordinate: Coordinate) -> Rgb {
+    match basins.label_at(height_map, coordinate) {
+        _ if height_map.is_low_point(coordinate) => LOW_POINT,
+        Some(label) => shade(
+            basin_colour(label),
+            height_map.get_height_at(coordinate),
+            height_map.wall_height,
+        ),
+        None => WALL,
+    }
+}
+
+/// Basins from largest to smallest, one per line, each with a swatch of its colour when `ansi`.
+fn legend(basins: &Basins, ansi: bool) -> String {
+    basins
+        .basins
+        .iter()
+        .enumerate()
+        .sorted_by_key(|&(label, basin)| (std::cmp::Reverse(basin.size), label))
+        .map(|(label, basin)| {
+            let swatch = if ansi {
+                let [r, g, b] = basin_colour(label);
+                format!("\x1b[48;2;{};{};{}m  \x1b[0m ", r, g, b)
+            } else {
+                String::new()
+            };
+            format!(
+                "{}basin {}: size {}, low point {},{}\n",
+                swatch, label, basin.size, basin.low_point.x, basin.low_point.y
+            )
+        })
+        .collect()
+}
+
+/// The heights as digits on a background coloured by basin, low points in bold on white, then the
+/// legend.
+fn render_ansi(height_map: &HeightMap) -> String {
+    let basins = height_map.basins();
+
+    let mut output = String::new();
+    for y in 0..height_map.height as i32 {
+        for x in 0..height_map.width as i32 {
+            let coordinate = Coordinate { x, y };
+            let [r, g, b] = cell_colour(height_map, &basins, coordinate);
+            output += &format!("\x1b[48;2;{};{};{}m", r, g, b);
+            let height = height_map.get_height_at(coordinate);
+            output += &if height_map.is_low_point(coordinate) {
+                format!("\x1b[1;30m{}\x1b[22;39m", height)
+            } else {
+                height.to_string()
+            };
+        }
+        output += "\x1b[0m\n";
+    }
+    output + "\n" + &legend(&basins, true)
+}
+
+/// A binary PPM of the basins with each cell drawn as a `scale` pixel square. The image has no room
+/// for text, so the legend comes back as plain text to show alongside it.
+fn write_ppm(
+    height_map: &HeightMap,
+    scale: usize,
+    mut writer: impl io::Write,
+) -> io::Result<String> {
+    let basins = height_map.basins();
+    write!(
+        writer,
+        "P6\n{} {}\n255\n",
+        height_map.width * scale,
+        height_map.height * scale
+    )?;
+
+    for y in 0..height_map.height as i32 {
+        let row = (0..height_map.width as i32)
+            .flat_map(|x| {
+                let colour = cell_colour(height_map, &basins, Coordinate { x, y });
+                std::iter::repeat_n(colour, scale)
+            })
+            .flatten()
+            .collect_vec();
+        for _ in 0..scale {
+            writer.write_all(&row)?;
+        }
+    }
+    Ok(legend(&basins, false))
+}
+
+fn strip_ansi(text: &str) -> String {
+    let mut plain = String::new();
+    let mut chars = text.chars();
+    while let Some(char) = chars.next() {
+        if char == '\x1b' {
+            chars.by_ref().find(|&c| c == 'm');
+        } else {
+            plain.push(char);
+        }
+    }
+    plain
+}
+
+#[test]
+fn example_ansi() {
+    let height_map = include_str!("example.txt").parse::<HeightMap>().unwrap();
+    let rendered = render_ansi(&height_map);
+
+    assert_eq!(
+        strip_ansi(&rendered),
+        include_str!("example.txt").to_string()
+            + "\n"
+            + "   basin 2: size 14, low point 2,2\n"
+            + "   basin 1: size 9, low point 9,0\n"
+            + "   basin 3: size 9, low point 6,4\n"
+            + "   basin 0: size 3, low point 1,0\n"
+    );
+    assert_eq!(rendered.matches("\x1b[1;30m").count(), 4);
+}
+
+#[test]
+fn low_point_style_ends_with_its_cell() {
+    let height_map = "19\n99".parse::<HeightMap>().unwrap();
+
+    assert!(render_ansi(&height_map).starts_with(
+        "\x1b[48;2;255;255;255m\x1b[1;30m1\x1b[22;39m\x1b[48;2;40;40;40m9\x1b[0m\n\
+         \x1b[48;2;40;40;40m9\x1b[48;2;40;40;40m9\x1b[0m\n\n"
+    ));
+}
+
+#[test]
+fn example_ppm() {
+    let height_map = include_str!("example.txt").parse::<HeightMap>().unwrap();
+    let mut image = vec![];
+    let legend = write_ppm(&height_map, 2, &mut image).unwrap();
+    assert_eq!(
+        legend.lines().next(),
+        Some("basin 2: size 14, low point 2,2")
+    );
+
+    let header = b"P6\n20 10\n255\n";
+    assert!(image.starts_with(header));
+    assert_eq!(image.len(), header.len() + 20 * 10 * 3);
+
+    let pixel = |x: usize, y: usize| {
+        let offset = header.len() + (y * 20 + x) * 3;
+        [image[offset], image[offset + 1], image[offset + 2]]
+    };
+    // the low point at 1,0 fills pixels 2..4 of the first two rows, and 2,0 is a wall
+    assert_eq!(pixel(2, 0), LOW_POINT);
+    assert_eq!(pixel(3, 1), LOW_POINT);
+    assert_eq!(pixel(4, 0), WALL);
+    assert_ne!(pixel(0, 0), WALL);
+}
@@ -1,109 +1,234 @@
+mod recovery;
+mod stream;
+
 use itertools::Itertools;
 
 fn input() -> impl Iterator<Item = &'static str> {
     include_str!("input.txt").lines()
 }
 
-fn find_first_illegal_char(input: &str) -> Option<char> {
-    let mut stack = vec![];
-    for char in input.chars() {
-        match char {
-            '(' | '[' | '{' | '<' => {
-                stack.push(char);
-            }
-            ')' => {
-                if !matches!(stack.pop(), Some('(')) {
-                    return Some(char);
-                }
-            }
-            ']' => {
-                if !matches!(stack.pop(), Some('[')) {
-                    return Some(char);
-                }
-            }
-            '}' => {
-                if !matches!(stack.pop(), Some('{')) {
-                    return Some(char);
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct Delimiter {
+    open: char,
+    close: char,
+    /// Awarded when `close` turns up where another closer was expected.
+    corruption_score: u64,
+    /// The digit `close` contributes to a completion score.
+    completion_score: u64,
+}
+
+/// The bracket pairs of a chunk dialect and how each scores.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct DelimiterSet(Vec<Delimiter>);
+
+impl DelimiterSet {
+    /// The navigation subsystem's four pairs, scored as in the puzzle.
+    fn navigation_subsystem() -> Self {
+        Self(vec![
+            Delimiter {
+                open: '(',
+                close: ')',
+                corruption_score: 3,
+                completion_score: 1,
+            },
+            Delimiter {
+                open: '[',
+                close: ']',
+                corruption_score: 57,
+                completion_score: 2,
+            },
+            Delimiter {
+                open: '{',
+                close: '}',
+                corruption_score: 1197,
+                completion_score: 3,
+            },
+            Delimiter {
+                open: '<',
+                close: '>',
+                corruption_score: 25137,
+                completion_score: 4,
+            },
+        ])
+    }
+
+    fn opened_by(&self, char: char) -> Option<&Delimiter> {
+        self.0.iter().find(|delimiter| delimiter.open == char)
+    }
+
+    fn closed_by(&self, char: char) -> Option<&Delimiter> {
+        self.0.iter().find(|delimiter| delimiter.close == char)
+    }
+
+    fn corruption_score(&self, char: char) -> u64 {
+        self.closed_by(char)
+            .map_or(0, |delimiter| delimiter.corruption_score)
+    }
+
+    /// One more than the largest completion digit, so that no two completions share a score. This
+    /// is the puzzle's 5 for the navigation subsystem.
+    fn completion_base(&self) -> u64 {
+        self.0
+            .iter()
+            .map(|delimiter| delimiter.completion_score)
+            .max()
+            .unwrap_or(0)
+            + 1
+    }
+
+    /// `None` if the score doesn't fit in a `u128`, around 55 closers for the navigation subsystem.
+    fn completion_score(&self, completion: &str) -> Option<u128> {
+        let base = self.completion_base() as u128;
+        completion.chars().try_fold(0u128, |acc, char| {
+            let digit = self
+                .closed_by(char)
+                .map_or(0, |delimiter| delimiter.completion_score);
+            acc.checked_mul(base)?.checked_add(digit as u128)
+        })
+    }
+
+    /// Classifies a line in one pass, keeping a stack of open delimiters and stopping at the first
+    /// closer that doesn't match the innermost one. Characters outside the set are ignored.
+    fn check(&self, line: &str) -> LineStatus {
+        let mut stack = vec![];
+        for (position, char) in line.chars().enumerate() {
+            if let Some(delimiter) = self.opened_by(char) {
+                stack.push(delimiter);
+            } else if self.closed_by(char).is_some() {
+                match stack.pop() {
+                    Some(open) if open.close == char => {}
+                    open => {
+                        return LineStatus::Corrupted {
+                            position,
+                            expected: open.map(|open| open.close),
+                            found: char,
+                        }
+                    }
                 }
             }
-            '>' => {
-                if !matches!(stack.pop(), Some('<')) {
-                    return Some(char);
-                }
+        }
+
+        if stack.is_empty() {
+            LineStatus::Complete
+        } else {
+            LineStatus::Incomplete {
+                completion: stack
+                    .iter()
+                    .rev()
+                    .map(|delimiter| delimiter.close)
+                    .collect(),
             }
-            _ => {}
         }
     }
-
-    None
 }
 
-fn illegal_char_score(char: char) -> u32 {
-    match char {
-        ')' => 3,
-        ']' => 57,
-        '}' => 1197,
-        '>' => 25137,
-        _ => 0,
-    }
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum LineStatus {
+    Complete,
+    /// `found` at character `position` doesn't close the innermost open chunk, which wanted
+    /// `expected`, or nothing was open at all.
+    Corrupted {
+        position: usize,
+        expected: Option<char>,
+        found: char,
+    },
+    /// Every chunk so far is well formed but some are still open; `completion` closes them.
+    Incomplete {
+        completion: String,
+    },
 }
 
-fn auto_complete(input: &str) -> String {
-    let mut stack = vec![];
-    for char in input.chars() {
-        match char {
-            '(' | '[' | '{' | '<' => {
-                stack.push(char);
-            }
-            ')' | ']' | '}' | '>' => {
-                stack.pop();
-            }
-            _ => {}
+#[test]
+fn other_dialects() {
+    let delimiters = DelimiterSet(vec![
+        Delimiter {
+            open: '«',
+            close: '»',
+            corruption_score: 7,
+            completion_score: 1,
+        },
+        Delimiter {
+            open: '/',
+            close: '\\',
+            corruption_score: 11,
+            completion_score: 2,
+        },
+    ]);
+
+    assert_eq!(
+        delimiters.check("«/»"),
+        LineStatus::Corrupted {
+            position: 2,
+            expected: Some('\\'),
+            found: '»'
         }
-    }
-    let mut completion = String::new();
-    while let Some(char) = stack.pop() {
-        completion.push(match char {
-            '(' => ')',
-            '[' => ']',
-            '{' => '}',
-            '<' => '>',
-            _ => panic!("bad input"),
-        });
-    }
-    completion
+    );
+    assert_eq!(delimiters.corruption_score('»'), 7);
+    assert_eq!(delimiters.check("«(]»"), LineStatus::Complete);
+
+    assert_eq!(
+        delimiters.check("«/\\«/"),
+        LineStatus::Incomplete {
+            completion: "\\»»".to_string()
+        }
+    );
+    assert_eq!(delimiters.completion_base(), 3);
+    assert_eq!(delimiters.completion_score("\\»»"), Some(22));
 }
 
-fn completion_score(char: char) -> u64 {
-    match char {
-        ')' => 1,
-        ']' => 2,
-        '}' => 3,
-        '>' => 4,
-        _ => 0,
-    }
+#[test]
+fn line_status() {
+    let delimiters = DelimiterSet::navigation_subsystem();
+
+    assert_eq!(
+        delimiters.check("{([(<{}[<>[]}>{[]{[(<()>"),
+        LineStatus::Corrupted {
+            position: 12,
+            expected: Some(']'),
+            found: '}'
+        }
+    );
+    assert_eq!(
+        delimiters.check("())"),
+        LineStatus::Corrupted {
+            position: 2,
+            expected: None,
+            found: ')'
+        }
+    );
+    assert_eq!(
+        delimiters.check("[({(<(())[]>[[{[]{<()<>>"),
+        LineStatus::Incomplete {
+            completion: "}}]])})]".to_string()
+        }
+    );
+    assert_eq!(delimiters.check("[<>({}){}[([])<>]]"), LineStatus::Complete);
 }
 
 #[test]
 fn part_one() {
+    let delimiters = DelimiterSet::navigation_subsystem();
     let answer = input()
-        .map(|line| find_first_illegal_char(line))
-        .flatten()
-        .map(illegal_char_score)
-        .sum::<u32>();
+        .filter_map(|line| match delimiters.check(line) {
+            LineStatus::Corrupted { found, .. } => Some(delimiters.corruption_score(found)),
+            _ => None,
+        })
+        .sum::<u64>();
 
     assert_eq!(answer, 296535);
 }
 
 #[test]
 fn part_two() {
+    let delimiters = DelimiterSet::navigation_subsystem();
     let completion_scores = input()
-        .filter_map(|line| find_first_illegal_char(line).is_none().then(|| line))
-        .map(auto_complete)
-        .map(|completion| {
-            completion
-                .chars()
-                .fold(0, |acc, char| (acc * 5) + completion_score(char))
+        .filter_map(|line| match delimiters.check(line) {
+            LineStatus::Incomplete { completion } => Some(
+                delimiters
+                    .completion_score(&completion)
+                    .expect("completion score overflows a u128"),
+            ),
+            _ => None,
         })
         .sorted()
         .collect_vec();
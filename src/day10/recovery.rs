@@ -0,0 +1,304 @@
+use {
+    super::{Delimiter, DelimiterSet},
+    std::{cmp::Reverse, ops::Range},
+};
+
+/// What the checker does at a closer that doesn't match the innermost open chunk.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Recovery {
+    /// Pretend the closer isn't there.
+    SkipBadCloser,
+    /// Pretend the chunks opened since the matching opener were closed just before it, or skip it
+    /// if nothing open matches.
+    InsertMissingCloser,
+}
+
+/// A problem with a line, located by a span of character positions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum SyntaxError {
+    Mismatched {
+        span: Range<usize>,
+        expected: char,
+        found: char,
+    },
+    Unopened {
+        span: Range<usize>,
+        found: char,
+    },
+    /// A chunk running from its opener to the end of the line without being closed.
+    Unclosed {
+        span: Range<usize>,
+        expected: char,
+    },
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Edit {
+    /// Insert `char` before the character at `position`, or at the end if it's the line length.
+    Insert {
+        position: usize,
+        char: char,
+    },
+    Delete {
+        position: usize,
+    },
+}
+
+impl DelimiterSet {
+    /// Every error in the line, carrying on past each one as `recovery` says.
+    fn check_all(&self, line: &str, recovery: Recovery) -> Vec<SyntaxError> {
+        let mut errors = vec![];
+        let mut stack = Vec::<(usize, &Delimiter)>::new();
+        let mut length = 0;
+        for (position, char) in line.chars().enumerate() {
+            length = position + 1;
+            if let Some(delimiter) = self.opened_by(char) {
+                stack.push((position, delimiter));
+                continue;
+            }
+            if self.closed_by(char).is_none() {
+                continue;
+            }
+
+            let span = position..position + 1;
+            match stack.last() {
+                Some((_, open)) if open.close == char => {
+                    stack.pop();
+                }
+                Some((_, open)) => {
+                    errors.push(SyntaxError::Mismatched {
+                        span,
+                        expected: open.close,
+                        found: char,
+                    });
+                    if recovery == Recovery::InsertMissingCloser {
+                        if let Some(matching) =
+                            stack.iter().rposition(|(_, open)| open.close == char)
+                        {
+                            stack.truncate(matching);
+                        }
+                    }
+                }
+                None => errors.push(SyntaxError::Unopened { span, found: char }),
+            }
+        }
+
+        errors.extend(
+            stack
+                .iter()
+                .rev()
+                .map(|&(position, open)| SyntaxError::Unclosed {
+                    span: position..length,
+                    expected: open.close,
+                }),
+        );
+        errors
+    }
+
+    /// The fewest insertions and deletions that make the line valid.
+    ///
+    /// An interval dynamic programme over the line's delimiters: each either pairs with a later
+    /// closer, splitting the interval in two, or costs one edit. Stray closers are deleted, and
+    /// unpaired openers closed at the end of the pair or line enclosing them.
+    ///
+    /// Takes O(n²) memory and O(n³) time in the number of delimiters, so it suits lines of a few
+    /// hundred delimiters like the puzzle's, not arbitrarily long ones.
+    fn minimal_edits(&self, line: &str) -> Vec<Edit> {
+        let delimiters = line
+            .chars()
+            .enumerate()
+            .filter(|&(_, char)| self.opened_by(char).is_some() || self.closed_by(char).is_some())
+            .collect::<Vec<_>>();
+        let n = delimiters.len();
+        let pairs = |i: usize, k: usize| {
+            self.opened_by(delimiters[i].1)
+                .is_some_and(|open| open.close == delimiters[k].1)
+        };
+
+        // cost[i][j] fixes delimiters[i..j]; partner[i][j] is what delimiters[i] pairs with there
+        let mut cost = vec![vec![0usize; n + 1]; n + 1];
+        let mut partner = vec![vec![None; n + 1]; n + 1];
+        for i in (0..n).rev() {
+            for j in i + 1..=n {
+                cost[i][j] = 1 + cost[i + 1][j];
+                for k in i + 1..j {
+                    if pairs(i, k) && cost[i + 1][k] + cost[k + 1][j] < cost[i][j] {
+                        cost[i][j] = cost[i + 1][k] + cost[k + 1][j];
+                        partner[i][j] = Some(k);
+                    }
+                }
+            }
+        }
+
+        let line_length = line.chars().count();
+        let mut edits = vec![];
+        // intervals still to resolve, with where an unpaired opener inside them gets closed
+        let mut intervals = vec![(0, n, line_length)];
+        while let Some((i, j, close_at)) = intervals.pop() {
+            if i >= j {
+                continue;
+            }
+            let (position, char) = delimiters[i];
+            match partner[i][j] {
+                Some(k) => {
+                    intervals.push((i + 1, k, delimiters[k].0));
+                    intervals.push((k + 1, j, close_at));
+                }
+                None => {
+                    edits.push((
+                        match self.opened_by(char) {
+                            Some(open) => Edit::Insert {
+                                position: close_at,
+                                char: open.close,
+                            },
+                            None => Edit::Delete { position },
+                        },
+                        position,
+                    ));
+                    intervals.push((i + 1, j, close_at));
+                }
+            }
+        }
+
+        // closers inserted at the same place go innermost first, i.e. latest opener first
+        edits.sort_by_key(|&(edit, source)| match edit {
+            Edit::Insert { position, .. } => (position, 0, Reverse(source)),
+            Edit::Delete { position } => (position, 1, Reverse(source)),
+        });
+        edits.into_iter().map(|(edit, _)| edit).collect()
+    }
+}
+
+/// The line with `edits` from [`DelimiterSet::minimal_edits`] made.
+fn apply(line: &str, edits: &[Edit]) -> String {
+    let mut fixed = String::new();
+    let mut edits = edits.iter().peekable();
+    let mut make_edits_at = |position: usize, fixed: &mut String| {
+        let mut deleted = false;
+        while let Some(edit) = edits.next_if(|edit| match edit {
+            Edit::Insert { position: p, .. } | Edit::Delete { position: p } => *p == position,
+        }) {
+            match *edit {
+                Edit::Insert { char, .. } => fixed.push(char),
+                Edit::Delete { .. } => deleted = true,
+            }
+        }
+        deleted
+    };
+
+    let mut length = 0;
+    for (position, char) in line.chars().enumerate() {
+        if !make_edits_at(position, &mut fixed) {
+            fixed.push(char);
+        }
+        length = position + 1;
+    }
+    make_edits_at(length, &mut fixed);
+    fixed
+}
+
+#[test]
+fn reports_every_error() {
+    let delimiters = DelimiterSet::navigation_subsystem();
+    let line = "(]<>}[";
+
+    assert_eq!(
+        delimiters.check_all(line, Recovery::SkipBadCloser),
+        [
+            SyntaxError::Mismatched {
+                span: 1..2,
+                expected: ')',
+                found: ']'
+            },
+            SyntaxError::Mismatched {
+                span: 4..5,
+                expected: ')',
+                found: '}'
+            },
+            SyntaxError::Unclosed {
+                span: 5..6,
+                expected: ']'
+            },
+            SyntaxError::Unclosed {
+                span: 0..6,
+                expected: ')'
+            },
+        ]
+    );
+
+    assert_eq!(
+        delimiters.check_all("([{)]", Recovery::InsertMissingCloser),
+        [
+            SyntaxError::Mismatched {
+                span: 3..4,
+                expected: '}',
+                found: ')'
+            },
+            SyntaxError::Unopened {
+                span: 4..5,
+                found: ']'
+            }
+        ]
+    );
+    assert_eq!(
+        delimiters.check_all("([{)]", Recovery::SkipBadCloser).len(),
+        5
+    );
+}
+
+#[test]
+fn minimal_edits() {
+    let delimiters = DelimiterSet::navigation_subsystem();
+
+    assert_eq!(
+        delimiters.minimal_edits("(]"),
+        [
+            Edit::Delete { position: 1 },
+            Edit::Insert {
+                position: 2,
+                char: ')'
+            }
+        ]
+    );
+    assert_eq!(
+        delimiters.minimal_edits("[({"),
+        [
+            Edit::Insert {
+                position: 3,
+                char: '}'
+            },
+            Edit::Insert {
+                position: 3,
+                char: ')'
+            },
+            Edit::Insert {
+                position: 3,
+                char: ']'
+            },
+        ]
+    );
+    assert_eq!(delimiters.minimal_edits("([]{})"), []);
+    assert_eq!(delimiters.minimal_edits("(()]").len(), 2);
+}
+
+#[test]
+fn apply_keeps_other_characters() {
+    let delimiters = DelimiterSet::navigation_subsystem();
+    let line = "(a\0]";
+
+    assert_eq!(apply(line, &delimiters.minimal_edits(line)), "(a\0)");
+}
+
+#[test]
+fn minimal_edits_fix_every_line() {
+    let delimiters = DelimiterSet::navigation_subsystem();
+
+    for line in super::input() {
+        let edits = delimiters.minimal_edits(line);
+        assert_eq!(
+            delimiters.check(&apply(line, &edits)),
+            super::LineStatus::Complete
+        );
+        assert!(edits.len() <= delimiters.check_all(line, Recovery::SkipBadCloser).len());
+    }
+}
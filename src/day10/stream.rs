@@ -0,0 +1,173 @@
+use {
+    super::{DelimiterSet, LineStatus},
+    std::io::{self, BufRead, Seek},
+};
+
+#[derive(Debug)]
+enum StreamError {
+    Io(io::Error),
+    /// A score on this line, counting from one, doesn't fit in a `u128`.
+    Overflow {
+        line: usize,
+    },
+}
+
+impl From<io::Error> for StreamError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SyntaxReport {
+    lines: usize,
+    corruption_total: u128,
+    /// The middle completion score, or `None` if no line was incomplete.
+    median_completion_score: Option<u128>,
+}
+
+impl DelimiterSet {
+    /// Reads the source from the start, calling `visit` with each line's number, counting from
+    /// one, and its status.
+    fn scan(
+        &self,
+        reader: &mut (impl BufRead + Seek),
+        mut visit: impl FnMut(usize, LineStatus) -> Result<(), StreamError>,
+    ) -> Result<(), StreamError> {
+        reader.rewind()?;
+        let (mut line, mut number) = (String::new(), 0);
+        while reader.read_line(&mut line)? > 0 {
+            number += 1;
+            visit(number, self.check(line.trim_end_matches(['\n', '\r'])))?;
+            line.clear();
+        }
+        Ok(())
+    }
+
+    /// Scores the lines in bounded memory, however many there are, by reading them more than once.
+    ///
+    /// The first pass totals the corruption scores and counts the incomplete lines. The median
+    /// completion score is then found a byte at a time, most significant first: each further pass
+    /// counts the scores sharing the bytes found so far by their next byte, which is enough to tell
+    /// which byte the median has. Only one line and 256 counters are held at once.
+    fn score_stream(&self, mut reader: impl BufRead + Seek) -> Result<SyntaxReport, StreamError> {
+        let mut report = SyntaxReport {
+            lines: 0,
+            corruption_total: 0,
+            median_completion_score: None,
+        };
+        let (mut incomplete, mut highest) = (0usize, 0u128);
+        self.scan(&mut reader, |line, status| {
+            report.lines = line;
+            let overflow = StreamError::Overflow { line };
+            match status {
+                LineStatus::Corrupted { found, .. } => {
+                    report.corruption_total = report
+                        .corruption_total
+                        .checked_add(self.corruption_score(found) as u128)
+                        .ok_or(overflow)?;
+                }
+                LineStatus::Incomplete { completion } => {
+                    let score = self.completion_score(&completion).ok_or(overflow)?;
+                    incomplete += 1;
+                    highest = highest.max(score);
+                }
+                LineStatus::Complete => {}
+            }
+            Ok(())
+        })?;
+        if incomplete == 0 {
+            return Ok(report);
+        }
+
+        // how many scores below the median share the bytes found so far
+        let mut rank = incomplete / 2;
+        let mut median = 0u128;
+        let top_byte = (127 - highest.leading_zeros().min(127)) / 8;
+        for shift in (0..=top_byte).rev().map(|byte| byte * 8) {
+            let mut counts = [0usize; 256];
+            self.scan(&mut reader, |_, status| {
+                if let LineStatus::Incomplete { completion } = status {
+                    let score = self.completion_score(&completion).unwrap();
+                    if score.checked_shr(shift + 8).unwrap_or(0)
+                        == median.checked_shr(shift + 8).unwrap_or(0)
+                    {
+                        counts[(score >> shift) as u8 as usize] += 1;
+                    }
+                }
+                Ok(())
+            })?;
+
+            let mut byte = 0;
+            while rank >= counts[byte] {
+                rank -= counts[byte];
+                byte += 1;
+            }
+            median |= (byte as u128) << shift;
+        }
+        report.median_completion_score = Some(median);
+        Ok(report)
+    }
+}
+
+#[test]
+fn streams_the_input() {
+    let delimiters = DelimiterSet::navigation_subsystem();
+    let report = delimiters
+        .score_stream(io::Cursor::new(include_str!("input.txt")))
+        .unwrap();
+
+    assert_eq!(report.lines, include_str!("input.txt").lines().count());
+    assert_eq!(report.corruption_total, 296535);
+    assert_eq!(report.median_completion_score, Some(4245130838));
+}
+
+#[test]
+fn deep_nesting_beyond_u64() {
+    let delimiters = DelimiterSet::navigation_subsystem();
+
+    let deep = "<".repeat(30) + "\n";
+    let report = delimiters.score_stream(io::Cursor::new(deep)).unwrap();
+    assert_eq!(report.median_completion_score, Some(5u128.pow(30) - 1));
+    assert!(report.median_completion_score.unwrap() > u64::MAX as u128);
+
+    let too_deep = "()\n".to_string() + &"<".repeat(60);
+    assert!(matches!(
+        delimiters.score_stream(io::Cursor::new(too_deep)),
+        Err(StreamError::Overflow { line: 2 })
+    ));
+}
+
+#[test]
+fn surfaces_read_errors() {
+    let delimiters = DelimiterSet::navigation_subsystem();
+
+    assert!(matches!(
+        delimiters.score_stream(io::Cursor::new(b"()\n(\xff\n")),
+        Err(StreamError::Io(error)) if error.kind() == io::ErrorKind::InvalidData
+    ));
+}
+
+#[test]
+fn median_matches_sorting() {
+    let delimiters = DelimiterSet::navigation_subsystem();
+    let lines = (1..200)
+        .map(|n| "<([{".repeat(n % 7) + &"[".repeat(n % 13) + &"(".repeat(n % 5 + 1) + "\n")
+        .collect::<String>();
+
+    let mut scores = lines
+        .lines()
+        .map(|line| match delimiters.check(line) {
+            LineStatus::Incomplete { completion } => delimiters.completion_score(&completion),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>()
+        .unwrap();
+    scores.sort_unstable();
+
+    let report = delimiters.score_stream(io::Cursor::new(lines)).unwrap();
+    assert_eq!(
+        report.median_completion_score,
+        Some(scores[scores.len() / 2])
+    );
+}
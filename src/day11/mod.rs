@@ -1,4 +1,7 @@
-use std::str::FromStr;
+use {
+    crate::digit_grid::{self, DigitGrid, ParseError},
+    std::{collections::VecDeque, str::FromStr},
+};
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 enum Octopus {
@@ -21,37 +24,49 @@ impl Octopus {
 }
 
 #[derive(Debug)]
-struct OctopusGrid(Vec<Octopus>);
+struct OctopusGrid {
+    octopi: Vec<Octopus>,
+    width: usize,
+    height: usize,
+}
 
 impl FromStr for OctopusGrid {
-    type Err = ();
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(Self(
-            s.chars()
-                .filter(|c| !c.is_whitespace())
-                .map(|c| c.to_digit(10).unwrap())
-                .map(Octopus::Charging)
-                .collect(),
-        ))
+        let DigitGrid {
+            digits,
+            width,
+            height,
+        } = digit_grid::parse(s)?;
+
+        Ok(Self {
+            octopi: digits.into_iter().map(Octopus::Charging).collect(),
+            width,
+            height,
+        })
     }
 }
 
 impl OctopusGrid {
+    fn len(&self) -> usize {
+        self.octopi.len()
+    }
+
     fn left(&self, index: usize) -> Option<usize> {
-        (index % 10 != 0).then(|| index - 1)
+        (!index.is_multiple_of(self.width)).then(|| index - 1)
     }
 
     fn right(&self, index: usize) -> Option<usize> {
-        ((index + 1) % 10 != 0).then(|| index + 1)
+        (!(index + 1).is_multiple_of(self.width)).then(|| index + 1)
     }
 
     fn up(&self, index: usize) -> Option<usize> {
-        (index > 9).then(|| index - 10)
+        (index >= self.width).then(|| index - self.width)
     }
 
     fn down(&self, index: usize) -> Option<usize> {
-        (index < 90).then(|| index + 10)
+        (index + self.width < self.len()).then(|| index + self.width)
     }
 
     fn adjacent(&self, index: usize) -> impl Iterator<Item = usize> {
@@ -69,41 +84,168 @@ impl OctopusGrid {
         .flatten()
     }
 
-    fn increase_energy(&mut self, index: usize) {
-        let octopus = self.0.get_mut(index).expect("bad index");
-        if !octopus.has_flashed() {
-            *octopus = octopus.increase_energy();
-            if octopus.has_flashed() {
-                for adjacent in self.adjacent(index) {
-                    self.increase_energy(adjacent);
-                }
-            }
+    /// Gives an octopus one more unit of energy, returning whether that made it flash.
+    fn increase_energy(&mut self, index: usize) -> bool {
+        let octopus = self.octopi.get_mut(index).expect("bad index");
+        if octopus.has_flashed() {
+            return false;
         }
-    }
-
-    fn count_flashed_octopi(&self) -> usize {
-        self.0
-            .iter()
-            .filter(|octopus| octopus.has_flashed())
-            .count()
+        *octopus = octopus.increase_energy();
+        octopus.has_flashed()
     }
 
     fn reset_flashed_octopi(&mut self) {
-        for octopus in &mut self.0 {
+        for octopus in &mut self.octopi {
             if octopus.has_flashed() {
                 *octopus = Octopus::Charging(0);
             }
         }
     }
 
-    fn step(&mut self) -> usize {
-        for i in 0..self.0.len() {
-            self.increase_energy(i);
+    /// Steps until every octopus flashes at once, returning which step that was.
+    fn first_step_all_flash(&mut self) -> usize {
+        (1..).find(|_| self.step().len() == self.len()).unwrap()
+    }
+
+    /// Charges every octopus, then works through a queue of flashes, each charging its
+    /// neighbours, until the cascade dies out.
+    fn step(&mut self) -> Step {
+        let mut queue = (0..self.len())
+            .filter(|&index| self.increase_energy(index))
+            .map(|index| (index, 0))
+            .collect::<VecDeque<_>>();
+
+        let mut flashes = vec![];
+        while let Some((index, wave)) = queue.pop_front() {
+            flashes.push(Flash {
+                x: index % self.width,
+                y: index / self.width,
+                wave,
+            });
+            for adjacent in self.adjacent(index) {
+                if self.increase_energy(adjacent) {
+                    queue.push_back((adjacent, wave + 1));
+                }
+            }
         }
-        let flashes = self.count_flashed_octopi();
+
         self.reset_flashed_octopi();
-        flashes
+        Step(flashes)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct Flash {
+    x: usize,
+    y: usize,
+    /// Zero for octopi that flashed from the step's own charge, otherwise one more than the wave
+    /// of the flash that set it off.
+    wave: usize,
+}
+
+/// Every flash in a step, in the order they happened.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Step(Vec<Flash>);
+
+impl Step {
+    fn len(&self) -> usize {
+        self.0.len()
     }
+
+    fn waves(&self) -> usize {
+        self.0.last().map_or(0, |flash| flash.wave + 1)
+    }
+
+    fn wave(&self, wave: usize) -> impl Iterator<Item = &Flash> {
+        self.0.iter().filter(move |flash| flash.wave == wave)
+    }
+}
+
+#[test]
+fn example() {
+    let example = "\
+5483143223
+2745854711
+5264556173
+6141336146
+6357385478
+4167524645
+2176841721
+6882881134
+4846848554
+5283751526";
+
+    let mut grid = example.parse::<OctopusGrid>().unwrap();
+    assert_eq!(
+        (0..100).fold(0, |flashes, _| flashes + grid.step().len()),
+        1656
+    );
+
+    let mut grid = example.parse::<OctopusGrid>().unwrap();
+    assert_eq!(grid.first_step_all_flash(), 195);
+}
+
+#[test]
+fn other_shapes() {
+    let mut grid = "11111\n19991\n19191\n19991\n11111"
+        .parse::<OctopusGrid>()
+        .unwrap();
+    let step = grid.step();
+    assert_eq!(step.len(), 9);
+    assert_eq!(grid.step().len(), 0);
+
+    assert_eq!(step.waves(), 2);
+    assert_eq!(
+        step.wave(0)
+            .map(|flash| (flash.x, flash.y))
+            .collect::<Vec<_>>(),
+        [
+            (1, 1),
+            (2, 1),
+            (3, 1),
+            (1, 2),
+            (3, 2),
+            (1, 3),
+            (2, 3),
+            (3, 3)
+        ]
+    );
+    assert_eq!(
+        step.0.last(),
+        Some(&Flash {
+            x: 2,
+            y: 2,
+            wave: 1
+        })
+    );
+
+    let wide = "3941\n7125\n8264";
+    let (mut wide_grid, mut tall_grid) = (
+        wide.parse::<OctopusGrid>().unwrap(),
+        digit_grid::transpose(wide).parse::<OctopusGrid>().unwrap(),
+    );
+    assert_eq!((tall_grid.width, tall_grid.height), (3, 4));
+    for _ in 0..50 {
+        assert_eq!(wide_grid.step().len(), tall_grid.step().len());
+    }
+    assert_eq!(
+        wide.parse::<OctopusGrid>().unwrap().first_step_all_flash(),
+        digit_grid::transpose(wide)
+            .parse::<OctopusGrid>()
+            .unwrap()
+            .first_step_all_flash()
+    );
+}
+
+#[test]
+fn large_cascade() {
+    let row = "8".repeat(300) + "9\n";
+    let mut grid = row.repeat(301).parse::<OctopusGrid>().unwrap();
+
+    let step = grid.step();
+    assert_eq!(step.len(), 301 * 301);
+    assert_eq!(step.waves(), 301);
+    assert!(step.wave(300).all(|flash| flash.x == 0));
 }
 
 #[test]
@@ -111,7 +253,7 @@ fn part_one() {
     let mut grid = include_str!("input.txt").parse::<OctopusGrid>().unwrap();
     let answer = (0..100)
         .into_iter()
-        .fold(0, |flashes, _| flashes + grid.step());
+        .fold(0, |flashes, _| flashes + grid.step().len());
 
     assert_eq!(answer, 1721);
 }
@@ -119,7 +261,7 @@ fn part_one() {
 #[test]
 fn part_two() {
     let mut grid = include_str!("input.txt").parse::<OctopusGrid>().unwrap();
-    let all_flash_at_step = (1..).into_iter().find(|_| grid.step() == 100).unwrap();
+    let all_flash_at_step = grid.first_step_all_flash();
 
     assert_eq!(all_flash_at_step, 298);
 }
@@ -0,0 +1,76 @@
+//! Rectangular grids of single digits, the input format shared by days 9 and 11.
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum ParseError {
+    Empty,
+    NotADigit(char),
+    RaggedRow { row: usize, length: usize },
+}
+
+pub(crate) struct DigitGrid {
+    /// Row by row, `width` digits to a row.
+    pub(crate) digits: Vec<u32>,
+    pub(crate) width: usize,
+    pub(crate) height: usize,
+}
+
+/// Reads one row per line, ignoring whitespace around each and blank lines after the last.
+pub(crate) fn parse(s: &str) -> Result<DigitGrid, ParseError> {
+    let mut digits = vec![];
+    let mut width = None;
+    let mut height = 0;
+    for (row, line) in s.trim_end().lines().map(str::trim).enumerate() {
+        let length = line.chars().count();
+        if *width.get_or_insert(length) != length {
+            return Err(ParseError::RaggedRow { row, length });
+        }
+
+        for char in line.chars() {
+            digits.push(char.to_digit(10).ok_or(ParseError::NotADigit(char))?);
+        }
+        height += 1;
+    }
+
+    match width {
+        Some(width) if width > 0 => Ok(DigitGrid {
+            digits,
+            width,
+            height,
+        }),
+        _ => Err(ParseError::Empty),
+    }
+}
+
+/// The grid's text with rows and columns swapped, to check nothing mixes up width and height.
+#[cfg(test)]
+pub(crate) fn transpose(input: &str) -> String {
+    let rows = input.lines().map(str::as_bytes).collect::<Vec<_>>();
+    (0..rows[0].len())
+        .map(|column| {
+            rows.iter()
+                .map(|row| row[column] as char)
+                .chain(Some('\n'))
+                .collect::<String>()
+        })
+        .collect()
+}
+
+#[test]
+fn rejects_malformed_grids() {
+    assert_eq!(
+        parse("123\n45\n678").err(),
+        Some(ParseError::RaggedRow { row: 1, length: 2 })
+    );
+    assert_eq!(parse("123\n4x6").err(), Some(ParseError::NotADigit('x')));
+    assert_eq!(parse("").err(), Some(ParseError::Empty));
+}
+
+#[test]
+fn ignores_whitespace_around_rows() {
+    let grid = parse("  123\r\n456 \n").unwrap();
+
+    assert_eq!(grid.digits, [1, 2, 3, 4, 5, 6]);
+    assert_eq!((grid.width, grid.height), (3, 2));
+    assert_eq!(parse("123\n456\n\n \n").unwrap().height, 2);
+    assert_eq!(transpose("123\n456"), "14\n25\n36\n");
+}
@@ -12,5 +12,6 @@ mod day11;
 mod day12;
 mod day13;
 mod day14;
+mod digit_grid;
 
 fn main() {}